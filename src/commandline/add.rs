@@ -1,24 +1,58 @@
+use crate::commandline;
+use crate::commandline::editor;
 use crate::config;
 use crate::database;
 use rusqlite::Connection;
+use std::collections::HashMap;
 use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// tags that can be set in the header of a note written in the editor
+const NOTE_TAGS: [&str; 4] = ["topic", "source", "context", "project"];
 
 pub fn add(
     config: &config::Config,
     entry_type: &str,
-    content: &str,
-    tags: Vec<String>,
+    content: Option<&str>,
+    mut tags: Vec<String>,
+    edit: bool,
 ) -> Result<(), Box<dyn Error>> {
     let conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    // `cerebra add note topic:x` leaves out the content, so the first tag ends up in its place
+    let content = match content {
+        Some(content) if entry_type == "note" && is_tag(content) => {
+            tags.insert(0, content.to_string());
+            None
+        }
+        content => content.map(String::from),
+    };
     let tags = database::init::get_tags(&tags);
+    if entry_type == "note" {
+        check_tags(&tags, None);
+    }
+
+    let (content, tags) = if entry_type == "note" && (edit || content.is_none()) {
+        compose_note(config, content.as_deref().unwrap_or(""), tags)?
+    } else {
+        match content {
+            Some(content) => (content, tags),
+            None => {
+                eprintln!("Missing content. Only notes can be written in the editor.");
+                std::process::exit(1);
+            }
+        }
+    };
+    let content = content.as_str();
 
     match entry_type {
         "note" => database::note::add(&config.note_path, &conn, content, tags),
         "idea" => database::idea::add(&conn, content, tags),
         "task" => database::task::add(&conn, content, tags),
         "project" => database::project::add(&conn, content, tags),
-        "writings" => database::writing::add(&conn, content, tags),
-        "code" => database::code::add(&conn, content, tags),
+        "writings" => database::writing::add(&config.journal_path, &conn, content, tags),
+        "code" => commandline::code::add(config, &conn, content, tags),
         _ => {
             eprintln!(
                 "Invalid entry type. Use 'note', 'idea', 'task', 'project', 'writings', or 'code'."
@@ -27,3 +61,126 @@ pub fn add(
         }
     }
 }
+
+// Opens the editor on a temporary file with the tags as a header, the note is everything below it
+fn compose_note(
+    config: &config::Config,
+    content: &str,
+    tags: HashMap<String, String>,
+) -> Result<(String, HashMap<String, String>), Box<dyn Error>> {
+    let path = std::env::temp_dir().join(format!("cerebra-note-{}.md", std::process::id()));
+    fs::write(&path, render_header(&tags, content))?;
+    editor::open(&config.editor, &path)?;
+    let text = fs::read_to_string(&path)?;
+
+    let (header, content) = parse_header(&text);
+    if let Some(header) = &header {
+        // the note is kept, so it doesn't have to be written again
+        check_tags(header, Some(&path));
+    }
+    fs::remove_file(&path)?;
+    if content.is_empty() {
+        eprintln!("The note is empty, nothing was added");
+        std::process::exit(1);
+    }
+
+    // without a header the tags from the command line are kept
+    Ok((content, header.unwrap_or(tags)))
+}
+
+fn render_header(tags: &HashMap<String, String>, content: &str) -> String {
+    let mut header = vec!["---".to_string()];
+    for key in NOTE_TAGS {
+        header.push(format!(
+            "{}: {}",
+            key,
+            tags.get(key).map_or("", String::as_str)
+        ));
+    }
+    header.push("---".to_string());
+
+    let mut text = format!("{}\n\n{}", header.join("\n"), content);
+    if !content.is_empty() && !content.ends_with('\n') {
+        text.push('\n');
+    }
+
+    text
+}
+
+// Splits the text into the tags of the header, if there is one, and the note below it. Empty
+// tags are left out.
+fn parse_header(text: &str) -> (Option<HashMap<String, String>>, String) {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("---") {
+        return (None, text.trim().to_string());
+    }
+
+    let mut tags = HashMap::new();
+    for line in lines.by_ref() {
+        if line.trim() == "---" {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            if !value.trim().is_empty() {
+                tags.insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+    }
+
+    let content = lines.collect::<Vec<&str>>().join("\n").trim().to_string();
+    (Some(tags), content)
+}
+
+// Exits on a tag notes don't have, pointing to the file of a note written in the editor
+fn check_tags(tags: &HashMap<String, String>, file: Option<&Path>) {
+    let mut invalid: Vec<&String> = tags
+        .keys()
+        .filter(|key| !NOTE_TAGS.contains(&key.as_str()))
+        .collect();
+    if invalid.is_empty() {
+        return;
+    }
+
+    invalid.sort();
+    eprintln!(
+        "Invalid tag '{}'. Use 'topic', 'source', 'context' or 'project'.",
+        invalid[0]
+    );
+    if let Some(file) = file {
+        eprintln!("The note was kept in {}", file.display());
+    }
+    std::process::exit(1);
+}
+
+// topic:science/physics is a tag, "Note: read this" and https://example.com are content
+fn is_tag(value: &str) -> bool {
+    match value.split_once(':') {
+        Some((key, _)) => NOTE_TAGS.contains(&key) && !value.contains(char::is_whitespace),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        let mut tags = HashMap::new();
+        tags.insert("topic".to_string(), "science/physics".to_string());
+        let text = render_header(&tags, "");
+        assert!(text.starts_with("---\ntopic: science/physics\nsource: \n"));
+
+        let (header, content) = parse_header(&format!("{}First line\n\nSecond paragraph\n", text));
+        assert_eq!(header, Some(tags));
+        assert_eq!(content, "First line\n\nSecond paragraph");
+
+        assert_eq!(
+            parse_header("  Just text\n"),
+            (None, "Just text".to_string())
+        );
+        assert!(is_tag("topic:science/physics"));
+        assert!(!is_tag("Note: read this"));
+        assert!(!is_tag("https://example.com"));
+    }
+}
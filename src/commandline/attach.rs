@@ -0,0 +1,12 @@
+use crate::config::Config;
+use crate::database;
+use rusqlite::Connection;
+use std::error::Error;
+use std::path::Path;
+
+pub fn attach(config: &Config, file: &Path, tags: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+    let tags = database::init::get_tags(&tags);
+
+    database::attachment::add(&config.misc_path, &conn, file, tags)
+}
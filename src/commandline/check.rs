@@ -0,0 +1,33 @@
+use crate::commandline::table;
+use crate::config::Config;
+use crate::database::citation;
+use rusqlite::Connection;
+use std::error::Error;
+
+// Links the citations of all notes to their sources, e.g. after importing a .bib file, and lists
+// the citation keys without a source
+pub fn check(config: &Config) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    let mut stmt = conn.prepare("SELECT id, content FROM note ORDER BY id")?;
+    let notes: Vec<(i64, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(Result::ok)
+        .collect();
+
+    let mut rows = Vec::new();
+    for (id, content) in &notes {
+        for key in citation::link(&conn, *id, content)? {
+            rows.push(vec![id.to_string(), key]);
+        }
+    }
+
+    if rows.is_empty() {
+        println!("All citations in {} notes resolve to a source", notes.len());
+        return Ok(());
+    }
+
+    table::print(&["note", "key"], &rows);
+    eprintln!("{} citation keys do not belong to any source", rows.len());
+    std::process::exit(1);
+}
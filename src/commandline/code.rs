@@ -0,0 +1,108 @@
+use crate::commandline::editor;
+use crate::config::Config;
+use crate::database::code;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::Path;
+
+// The snippet is taken from file: and lines:, piped in, e.g. `cat main.rs | cerebra add code main.rs`,
+// or written in the editor
+pub fn add(
+    config: &Config,
+    conn: &Connection,
+    name: &str,
+    tags: HashMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    let mut language = match tags.get("lang") {
+        Some(lang) => Some(
+            code::parse_language(lang)
+                .map(String::from)
+                .unwrap_or(lang.to_lowercase()),
+        ),
+        None => code::detect_language(name, "")
+            .or(tags
+                .get("file")
+                .and_then(|file| code::detect_language(file, "")))
+            .map(String::from),
+    };
+
+    let snippet = if let Some(file) = tags.get("file") {
+        let lines = match tags.get("lines") {
+            Some(lines) => Some(code::parse_lines(lines)?),
+            None => None,
+        };
+        code::read_source(Path::new(file), lines)?
+    } else if io::stdin().is_terminal() {
+        let extension = language.as_deref().map_or("txt", code::get_extension);
+        let path =
+            std::env::temp_dir().join(format!("cerebra-code-{}.{}", std::process::id(), extension));
+        fs::write(&path, "")?;
+        editor::open(&config.editor, &path)?;
+        let snippet = fs::read_to_string(&path)?;
+        fs::remove_file(&path)?;
+        snippet
+    } else {
+        let mut snippet = String::new();
+        io::stdin().read_to_string(&mut snippet)?;
+        snippet
+    };
+
+    if snippet.trim().is_empty() {
+        eprintln!("The snippet is empty, nothing was added");
+        std::process::exit(1);
+    }
+
+    if language.is_none() {
+        language = code::detect_language(name, &snippet).map(String::from);
+    }
+    let language = match language {
+        Some(language) => language,
+        None => {
+            eprintln!(
+                "Could not detect the language of '{}'. Use lang:<language>.",
+                name
+            );
+            std::process::exit(1);
+        }
+    };
+
+    code::add(&config.code_path, conn, name, &language, &snippet, tags)
+}
+
+pub fn cat(config: &Config, id: u64) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+    print!("{}", code::get_snippet(&conn, id)?);
+
+    Ok(())
+}
+
+pub fn extract(config: &Config) -> Result<(), Box<dyn Error>> {
+    let mut conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    let mut created = Vec::new();
+    let transaction = conn.transaction()?;
+    let result = code::extract(
+        &config.note_path,
+        &config.code_path,
+        &transaction,
+        &mut created,
+    )
+    .and_then(|_| Ok(transaction.commit()?));
+
+    // the entries of the snippet files are gone after a rollback
+    if result.is_err() {
+        for path in &created {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    result
+}
+
+pub fn check(config: &Config) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+    code::check(&conn)
+}
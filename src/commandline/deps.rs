@@ -0,0 +1,64 @@
+use crate::config::Config;
+use crate::database::dependency::{self, DependencyGraph};
+use petgraph::Direction;
+use rusqlite::{params, Connection};
+use std::error::Error;
+
+pub fn deps(config: &Config, id: u64) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+    let id = id as i64;
+
+    let root = match describe(&conn, id) {
+        Ok(root) => root,
+        Err(_) => {
+            eprintln!("Task {} does not exist", id);
+            std::process::exit(1);
+        }
+    };
+
+    let graph = DependencyGraph::new(&dependency::get_edges(&conn)?);
+
+    println!("{}", root);
+    println!("Depends on:");
+    print_tree(&conn, &graph, id, Direction::Outgoing, "")?;
+    println!("Blocks:");
+    print_tree(&conn, &graph, id, Direction::Incoming, "")?;
+
+    Ok(())
+}
+
+// Dependencies are acyclic, so walking them recursively always terminates
+fn print_tree(
+    conn: &Connection,
+    graph: &DependencyGraph,
+    id: i64,
+    direction: Direction,
+    prefix: &str,
+) -> Result<(), Box<dyn Error>> {
+    let neighbors = graph.neighbors(id, direction);
+
+    if neighbors.is_empty() && prefix.is_empty() {
+        println!("  (none)");
+    }
+
+    for (i, neighbor) in neighbors.iter().enumerate() {
+        let is_last = i == neighbors.len() - 1;
+        let branch = if is_last { "└── " } else { "├── " };
+        println!("{}{}{}", prefix, branch, describe(conn, *neighbor)?);
+
+        let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+        print_tree(conn, graph, *neighbor, direction, &child_prefix)?;
+    }
+
+    Ok(())
+}
+
+fn describe(conn: &Connection, id: i64) -> Result<String, rusqlite::Error> {
+    let (name, status): (String, String) = conn.query_row(
+        "SELECT name, status FROM task WHERE id = ?",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    Ok(format!("{}: '{}' [{}]", id, name, status))
+}
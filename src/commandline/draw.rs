@@ -87,14 +87,11 @@ pub fn draw(conn: &Connection) -> Result<(), Box<dyn StdError>> {
                 _ => {}
             }
         } else if let Event::Mouse(mouse_event) = event::read()? {
-            match mouse_event.kind {
-                MouseEventKind::Down(_) => {
-                    let y = mouse_event.column as usize;
-                    if y < tables.len() {
-                        selected_table = Some(y);
-                    }
+            if let MouseEventKind::Down(_) = mouse_event.kind {
+                let y = mouse_event.column as usize;
+                if y < tables.len() {
+                    selected_table = Some(y);
                 }
-                _ => {}
             }
         }
     }
@@ -202,14 +199,13 @@ fn draw_ui(f: &mut Frame, tables: &[Table], selected_table: Option<usize>) {
         |f: &mut Frame, area: Rect, tables: &[&Table], selected_table: Option<usize>| {
             let items: Vec<ListItem> = tables
                 .iter()
-                .enumerate()
-                .map(|(_, table)| {
-                    let is_selected = selected_table.map_or(false, |selected| {
-                        tables.get(selected).map_or(false, |t| t.name == table.name)
+                .map(|table| {
+                    let is_selected = selected_table.is_some_and(|selected| {
+                        tables.get(selected).is_some_and(|t| t.name == table.name)
                     });
 
                     let content = vec![Line::from(Span::styled(
-                        format!("{}", table.name),
+                        table.name.to_string(),
                         Style::default()
                             .fg(if is_selected { MAROON } else { TEXT })
                             .add_modifier(Modifier::BOLD),
@@ -237,7 +233,7 @@ fn draw_ui(f: &mut Frame, tables: &[Table], selected_table: Option<usize>) {
 
     let render_selected_table = |f: &mut Frame, area: Rect, table: &Table| {
         let mut content = vec![Line::from(Span::styled(
-            format!("{}", table.name),
+            table.name.to_string(),
             Style::default().fg(MAROON).add_modifier(Modifier::BOLD),
         ))];
 
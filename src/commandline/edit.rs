@@ -0,0 +1,90 @@
+use crate::commandline::editor;
+use crate::config::Config;
+use crate::database::{code, note, writing};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::error::Error;
+
+pub fn edit(config: &Config, entry_type: &str, id: u64) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    match entry_type {
+        "note" => edit_note(config, &conn, id as i64),
+        "writings" => {
+            let path: Option<Option<String>> = conn
+                .query_row(
+                    "SELECT path FROM writing WHERE id = ?",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let path = match path {
+                Some(Some(path)) => path,
+                Some(None) => return Err(format!("Writing {} has no file", id).into()),
+                None => {
+                    eprintln!("Writing {} does not exist", id);
+                    std::process::exit(1);
+                }
+            };
+
+            editor::open(&config.editor, path.as_ref())?;
+            writing::refresh(&conn)
+        }
+        "code" => {
+            editor::open(&config.editor, &code::get_path(&conn, id)?)?;
+            code::update_hash(&conn, id)
+        }
+        _ => {
+            eprintln!("Invalid entry type. Use 'note', 'writings', or 'code'.");
+            std::process::exit(1);
+        }
+    }
+}
+
+// Opens the file of the note at its first line and writes the edited block back into the database
+fn edit_note(config: &Config, conn: &Connection, id: i64) -> Result<(), Box<dyn Error>> {
+    let content: Option<String> = conn
+        .query_row(
+            "SELECT content FROM note WHERE id = ?",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let content = match content {
+        Some(content) => content,
+        None => {
+            eprintln!("Note {} does not exist", id);
+            std::process::exit(1);
+        }
+    };
+
+    let (file, line) = match note::locate(&config.note_path, id, &content)? {
+        Some(location) => location,
+        None => {
+            eprintln!("Note {} was not found in the notes directory", id);
+            std::process::exit(1);
+        }
+    };
+    editor::open_at(&config.editor, &file, line)?;
+
+    match note::read_block(&config.note_path, id)? {
+        // older versions stored notes with surrounding whitespace
+        Some(new_content) if new_content == content.trim() => {
+            println!("Note {} is unchanged", id)
+        }
+        Some(new_content) if new_content.is_empty() => eprintln!(
+            "Note {} is empty now, use `cerebra rm note {}` to delete it",
+            id, id
+        ),
+        Some(new_content) => {
+            note::set_content(conn, id, &new_content)?;
+            println!("Modified note {}: '{}'", id, new_content);
+        }
+        None => eprintln!(
+            "The anchors of note {} were removed from {}, the note was not modified",
+            id,
+            file.display()
+        ),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+
+// The editor setting is run by the shell like $EDITOR, so it may contain arguments and quotes,
+// e.g. `code --wait` or `"/opt/My Editor/bin/edit" -w`
+pub fn open(editor: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+    run(editor, &[], path)
+}
+
+// Opens the file at a line with +<line>, which vi, nano, emacs and most other editors understand
+pub fn open_at(editor: &str, path: &Path, line: usize) -> Result<(), Box<dyn Error>> {
+    run(editor, &[format!("+{}", line)], path)
+}
+
+fn run(editor: &str, arguments: &[String], path: &Path) -> Result<(), Box<dyn Error>> {
+    if editor.trim().is_empty() {
+        return Err("No editor configured".into());
+    }
+
+    // the arguments are passed as positional parameters, so paths with spaces stay intact
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg("sh")
+        .args(arguments)
+        .arg(path)
+        .status()?;
+    if !status.success() {
+        return Err(format!("Editor '{}' exited with {}", editor, status).into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,238 @@
+use crate::config::Config;
+use crate::database::{bibtex, citation, init, project};
+use chrono::{Local, NaiveDate, NaiveDateTime, Utc};
+use rusqlite::Connection;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// id, name, status, priority, due, scheduled, modified and project id of a task
+type TaskRow = (
+    i64,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    String,
+    Option<i64>,
+);
+
+pub fn export(
+    config: &Config,
+    export_type: &str,
+    output: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    if export_type == "notes" {
+        let output = output.unwrap_or(Path::new("cerebra-notes"));
+        let count = export_notes(&conn, &config.note_path, output)?;
+        println!("Exported {} note files to {}", count, output.display());
+        return Ok(());
+    }
+
+    let (content, default_output) = match export_type {
+        "ics" => (export_ics(&conn)?, "cerebra.ics"),
+        "bibtex" => (bibtex::export(&conn)?, "cerebra.bib"),
+        _ => {
+            eprintln!("Invalid export type. Use 'ics', 'bibtex' or 'notes'.");
+            std::process::exit(1);
+        }
+    };
+
+    let output = output.unwrap_or(Path::new(default_output));
+    fs::write(output, content)?;
+    println!("Exported {} to {}", export_type, output.display());
+
+    Ok(())
+}
+
+// Copies the note files into the output directory with a bibliography of the sources they cite,
+// the note files themselves stay as they are
+fn export_notes(
+    conn: &Connection,
+    note_directory: &Path,
+    output: &Path,
+) -> Result<usize, Box<dyn Error>> {
+    let output = std::env::current_dir()?.join(output);
+    if output.starts_with(note_directory) {
+        eprintln!("Notes cannot be exported into the notes directory");
+        std::process::exit(1);
+    }
+
+    let files = init::get_markdown_files(note_directory)?;
+    for file in &files {
+        let path = output.join(file.strip_prefix(note_directory)?);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = fs::read_to_string(file)?;
+        fs::write(path, citation::render_bibliography(conn, &content)?)?;
+    }
+
+    Ok(files.len())
+}
+
+// UIDs are derived from the entry ids, so calendars update events on re-export instead of
+// duplicating them
+fn export_ics(conn: &Connection) -> Result<String, Box<dyn Error>> {
+    let dtstamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//kitsuki-net//cerebra//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+
+    let mut stmt = conn.prepare(
+        "SELECT task.id, task.name, task.status, task.priority, task.due, task.scheduled,
+            task.modified, project.id
+        FROM task
+        LEFT JOIN TaskHasProject ON TaskHasProject.task_id = task.id
+        LEFT JOIN project ON project.id = TaskHasProject.project_id
+        WHERE task.status IN ('pending', 'done')
+        AND (task.due IS NOT NULL OR task.scheduled IS NOT NULL)
+        ORDER BY task.id",
+    )?;
+    let tasks: Vec<TaskRow> = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+                row.get(7)?,
+            ))
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    for (id, name, status, priority, due, scheduled, modified, project_id) in tasks {
+        lines.push("BEGIN:VTODO".to_string());
+        lines.push(format!("UID:task-{}@cerebra", id));
+        lines.push(format!("DTSTAMP:{}", dtstamp));
+        // timestamps are stored in local time, LAST-MODIFIED has to be UTC
+        if let Some(modified) = NaiveDateTime::parse_from_str(&modified, "%Y-%m-%d %H:%M:%S")
+            .ok()
+            .and_then(|modified| modified.and_local_timezone(Local).single())
+        {
+            lines.push(format!(
+                "LAST-MODIFIED:{}",
+                modified.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")
+            ));
+        }
+        lines.push(format!("SUMMARY:{}", escape(&name)));
+        if let Some(scheduled) = scheduled {
+            lines.push(format!("DTSTART;VALUE=DATE:{}", format_date(&scheduled)?));
+        }
+        if let Some(due) = due {
+            lines.push(format!("DUE;VALUE=DATE:{}", format_date(&due)?));
+        }
+        lines.push(format!(
+            "STATUS:{}",
+            if status == "done" {
+                "COMPLETED"
+            } else {
+                "NEEDS-ACTION"
+            }
+        ));
+        match priority.as_deref() {
+            Some("H") => lines.push("PRIORITY:1".to_string()),
+            Some("M") => lines.push("PRIORITY:5".to_string()),
+            Some("L") => lines.push("PRIORITY:9".to_string()),
+            _ => {}
+        }
+        if let Some(project_id) = project_id {
+            lines.push(format!(
+                "CATEGORIES:{}",
+                escape(&project::get_path(conn, project_id)?)
+            ));
+        }
+        lines.push("END:VTODO".to_string());
+    }
+
+    // project deadlines are all-day events, calendars show those more reliably than todos
+    let mut stmt = conn
+        .prepare("SELECT id, target, status FROM project WHERE target IS NOT NULL ORDER BY id")?;
+    let projects: Vec<(i64, String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .filter_map(Result::ok)
+        .collect();
+
+    for (id, target, status) in projects {
+        let target = NaiveDate::parse_from_str(&target, "%Y-%m-%d")?;
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:project-{}@cerebra", id));
+        lines.push(format!("DTSTAMP:{}", dtstamp));
+        lines.push(format!(
+            "SUMMARY:{}",
+            escape(&format!("Deadline: {}", project::get_path(conn, id)?))
+        ));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", target.format("%Y%m%d")));
+        lines.push(format!(
+            "DTEND;VALUE=DATE:{}",
+            target.succ_opt().unwrap_or(target).format("%Y%m%d")
+        ));
+        lines.push(format!(
+            "DESCRIPTION:{}",
+            escape(&format!("Status: {}", status))
+        ));
+        lines.push("TRANSP:TRANSPARENT".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let content: Vec<String> = lines.iter().map(|line| fold(line)).collect();
+    Ok(format!("{}\r\n", content.join("\r\n")))
+}
+
+fn format_date(date: &str) -> Result<String, Box<dyn Error>> {
+    Ok(NaiveDate::parse_from_str(date, "%Y-%m-%d")?
+        .format("%Y%m%d")
+        .to_string())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Lines longer than 75 octets are folded onto continuation lines starting with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+
+    for character in line.chars() {
+        let width = character.len_utf8();
+        if length + width > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(character);
+        length += width;
+    }
+
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_and_fold() {
+        assert_eq!(escape("a, b; c\\d\ne"), "a\\, b\\; c\\\\d\\ne");
+
+        let line = format!("SUMMARY:{}", "x".repeat(100));
+        let folded = fold(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+}
@@ -22,7 +22,7 @@ pub fn _add() {
     println!("  type               The type of entry to add to the database, e.g. 'note'");
     println!("  entry              The entry to add to the database");
     println!("  tags               The tags to associate with the entry, e.g 'project:foo'");
-    println!("");
+    println!();
     println!("Examples:");
     println!("  cerebra add note new note");
     println!("  cerebra add note new note project:foo");
@@ -35,7 +35,7 @@ pub fn _remove() {
     println!();
     println!("Arguments:");
     println!("  id                 The id of the entry to remove from the database");
-    println!("");
+    println!();
     println!("Examples:");
     println!("  cerebra rm 1");
 }
@@ -49,7 +49,7 @@ pub fn _mod_entry() {
     println!("  id                 The id of the entry to modify in the database");
     println!("  entry              The new entry to replace the old entry with");
     println!("  tags               The new tags to replace the old tags with");
-    println!("");
+    println!();
     println!("Examples:");
     println!("  cerebra mod 1 new entry");
     println!("  cerebra mod 1 new entry project:foo");
@@ -64,7 +64,7 @@ pub fn _config() {
     println!("Arguments:");
     println!("  key                The key to configure, e.g. 'db_path'");
     println!("  value              The value to set the key to, e.g. 'cerebra.db'");
-    println!("");
+    println!();
     println!("Examples:");
     println!("  cerebra config db_path cerebra.db");
 }
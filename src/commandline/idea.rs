@@ -0,0 +1,16 @@
+use crate::config::Config;
+use crate::database::{idea, note};
+use rusqlite::Connection;
+use std::error::Error;
+
+pub fn promote(config: &Config, id: u64, name: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let mut conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    // the project, its links and the idea stage change together or not at all
+    let transaction = conn.transaction()?;
+    let (note_id, content, topic) = idea::promote(&transaction, id, name)?;
+    transaction.commit()?;
+
+    // written after the commit, so a failed promotion doesn't leave a note behind
+    note::add_file(&config.note_path, &conn, note_id, &content, &topic)
+}
@@ -0,0 +1,25 @@
+use crate::config::Config;
+use crate::database::{bibtex, taskwarrior};
+use rusqlite::Connection;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+pub fn import(config: &Config, import_type: &str, file: &Path) -> Result<(), Box<dyn Error>> {
+    let mut conn = Connection::open(&config.db_path).expect("Failed to open database");
+    let content = fs::read_to_string(file)?;
+
+    // a broken file should not leave half of it in the database
+    let transaction = conn.transaction()?;
+    match import_type {
+        "taskwarrior" => taskwarrior::import(&transaction, &taskwarrior::parse(&content)?)?,
+        "bibtex" => bibtex::import(&transaction, &bibtex::parse(&content)?)?,
+        _ => {
+            eprintln!("Invalid import type. Use 'taskwarrior' or 'bibtex'.");
+            std::process::exit(1);
+        }
+    }
+    transaction.commit()?;
+
+    Ok(())
+}
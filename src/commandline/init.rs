@@ -1,3 +1,4 @@
+use rusqlite::Connection;
 use std::error::Error;
 use std::path::Path;
 
@@ -5,9 +6,9 @@ use crate::config;
 use crate::database;
 
 pub fn init(config_path: &Path, config: &config::Config) -> Result<(), Box<dyn Error>> {
-    config::create_config(config_path, &config).expect("Failed to create config file");
+    config::create_config(config_path, config).expect("Failed to create config file");
 
-    create_paths(&config).expect("Failed to create directories");
+    create_paths(config).expect("Failed to create directories");
 
     if !config.log_path.exists() {
         std::fs::File::create(&config.log_path)?;
@@ -21,6 +22,8 @@ pub fn init(config_path: &Path, config: &config::Config) -> Result<(), Box<dyn E
             .expect("Failed to create database tables");
         println!("Created database: {}", config.db_path.display());
     } else {
+        let mut conn = Connection::open(&config.db_path)?;
+        database::init::migrate(&mut conn).expect("Failed to migrate database");
         println!("Database already exists: {}", config.db_path.display());
     }
 
@@ -54,6 +54,8 @@ The configuration file can contain the following keys:
 - code_path: path to the directory where code snippets are stored
 - theme: theme to use for the TUI
 - editor: editor to use for editing entries
+- urgency_*: coefficients used by `cerebra next` to rank tasks (due, priority_h, priority_m,
+  priority_l, age, project, blocking, blocked)
 
 "#;
 
@@ -0,0 +1,39 @@
+use crate::commandline::editor;
+use crate::config::Config;
+use crate::database::writing;
+use chrono::Local;
+use rusqlite::Connection;
+use std::error::Error;
+use std::fs;
+
+const TEMPLATE: &str =
+    "<!-- Tag this entry with words like topic:science/physics or context:home -->\n";
+
+pub fn journal(config: &Config) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+    let today = Local::now().date_naive();
+    let date = today.format("%Y-%m-%d").to_string();
+
+    // journal_path/2026/10/2026-10-18.md
+    let directory = config
+        .journal_path
+        .join(today.format("%Y").to_string())
+        .join(today.format("%m").to_string());
+    let path = directory.join(format!("{}.md", date));
+
+    if !path.exists() {
+        fs::create_dir_all(&directory)?;
+        fs::write(
+            &path,
+            format!("# {}\n\n{}\n", today.format("%A, %Y-%m-%d"), TEMPLATE),
+        )?;
+        println!("Created journal entry {}", path.display());
+    }
+
+    editor::open(&config.editor, &path)?;
+
+    let content = fs::read_to_string(&path)?;
+    writing::register_journal(&conn, &path, &date, &content)?;
+
+    Ok(())
+}
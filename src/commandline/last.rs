@@ -1,12 +1,25 @@
-use crate::database::init::get_content_by_id;
+use crate::commandline::table;
+use crate::database::init::{get_content_by_id, get_date};
 use rusqlite::{params, Connection};
 use std::cmp;
 use std::error::Error;
 use std::path::Path;
 
-pub fn last(db_path: &Path, amount: u64, entry_type: &str) -> Result<(), Box<dyn Error>> {
+pub fn last(
+    db_path: &Path,
+    amount: u64,
+    entry_type: &str,
+    all: bool,
+) -> Result<(), Box<dyn Error>> {
     let conn = Connection::open(db_path).expect("Failed to open database");
 
+    if entry_type == "task" {
+        return last_tasks(&conn, amount, all);
+    }
+    if entry_type == "idea" {
+        return last_ideas(&conn, amount, all);
+    }
+
     let query = format!("SELECT * FROM {} ORDER BY id DESC LIMIT ?", entry_type);
     let mut stmt = conn.prepare(&query)?;
     let mut rows = stmt.query(params![amount])?;
@@ -76,6 +89,64 @@ pub fn last(db_path: &Path, amount: u64, entry_type: &str) -> Result<(), Box<dyn
     Ok(())
 }
 
+// Waiting and deleted tasks are only listed with --all, recurring templates never are
+fn last_tasks(conn: &Connection, amount: u64, all: bool) -> Result<(), Box<dyn Error>> {
+    let query = "SELECT id, name, status, priority, due, wait FROM task
+        WHERE status != 'recurring'
+        AND (?1 OR (status != 'deleted' AND (wait IS NULL OR wait <= ?2)))
+        ORDER BY id DESC LIMIT ?3";
+    let mut stmt = conn.prepare(query)?;
+    let rows: Vec<Vec<String>> = stmt
+        .query_map(params![all, get_date(), amount], |row| {
+            Ok(vec![
+                row.get::<usize, i64>(0)?.to_string(),
+                row.get::<usize, String>(1)?,
+                row.get::<usize, String>(2)?,
+                row.get::<usize, Option<String>>(3)?.unwrap_or_default(),
+                row.get::<usize, Option<String>>(4)?.unwrap_or_default(),
+                row.get::<usize, Option<String>>(5)?.unwrap_or_default(),
+            ])
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    println!("Last {} tasks:", amount);
+    table::print(
+        &["id", "content", "status", "priority", "due", "wait"],
+        &rows,
+    );
+
+    Ok(())
+}
+
+// Promoted and discarded ideas are only listed with --all
+fn last_ideas(conn: &Connection, amount: u64, all: bool) -> Result<(), Box<dyn Error>> {
+    let query = "SELECT idea.id, idea.name, idea.stage, GROUP_CONCAT(topic.name, ', ')
+        FROM idea
+        LEFT JOIN IdeaHasTopic ON IdeaHasTopic.idea_id = idea.id
+        LEFT JOIN topic ON topic.id = IdeaHasTopic.topic_id
+        WHERE ?1 OR idea.stage NOT IN ('promoted', 'discarded')
+        GROUP BY idea.id
+        ORDER BY idea.id DESC LIMIT ?2";
+    let mut stmt = conn.prepare(query)?;
+    let rows: Vec<Vec<String>> = stmt
+        .query_map(params![all, amount], |row| {
+            Ok(vec![
+                row.get::<usize, i64>(0)?.to_string(),
+                row.get::<usize, String>(1)?,
+                row.get::<usize, String>(2)?,
+                row.get::<usize, Option<String>>(3)?.unwrap_or_default(),
+            ])
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    println!("Last {} ideas:", amount);
+    table::print(&["id", "content", "stage", "topics"], &rows);
+
+    Ok(())
+}
+
 fn calculate_max_widths<'a>(
     conn: &Connection,
     rows: &mut rusqlite::Rows<'a>,
@@ -1,9 +1,27 @@
 pub mod add;
+pub mod attach;
+pub mod check;
+pub mod code;
+pub mod deps;
 pub mod draw;
+pub mod edit;
+pub mod editor;
+pub mod export;
 pub mod help;
+pub mod idea;
+pub mod import;
 pub mod init;
 pub mod intro;
+pub mod journal;
 pub mod last;
 pub mod modify;
+pub mod next;
+pub mod project;
 pub mod remove;
+pub mod report;
 pub mod search;
+pub mod source;
+pub mod sync;
+pub mod table;
+pub mod timesheet;
+pub mod writing;
@@ -9,23 +9,29 @@ pub fn mod_entry(
     id: u64,
     tags: Vec<String>,
 ) -> Result<(), Box<dyn Error>> {
-    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+    let mut conn = Connection::open(&config.db_path).expect("Failed to open database");
     let tags = database::init::get_tags(&tags);
 
-    let _ = match entry_type {
-        "note" => database::note::modify(&config.note_path, &conn, id, tags),
-        "idea" => database::idea::modify(&conn, tags),
-        "task" => database::task::modify(&conn, tags),
-        "project" => database::project::modify(&conn, tags),
-        "writings" => database::writing::modify(&conn, tags),
-        "code" => database::code::modify(&conn, tags),
+    // tags are applied one by one, an invalid tag must not leave the ones before it changed
+    let transaction = conn.transaction()?;
+    let result = match entry_type {
+        "note" => database::note::modify(&config.note_path, &transaction, id, tags),
+        "idea" => database::idea::modify(&transaction, id, tags),
+        "task" => database::task::modify(&transaction, id, tags),
+        "project" => database::project::modify(&transaction, id, tags),
+        "writings" => database::writing::modify(&transaction, id, tags),
+        "code" => database::code::modify(&transaction, id, tags),
+        "source" => database::source::modify(&transaction, id, tags),
         _ => {
             eprintln!(
-                "Invalid entry type. Use 'note', 'idea', 'task', 'project', 'writings', or 'code'."
+                "Invalid entry type. Use 'note', 'idea', 'task', 'project', 'writings', 'code', or 'source'."
             );
             std::process::exit(1);
         }
     };
+    if result.is_ok() {
+        transaction.commit()?;
+    }
 
-    Ok(())
+    result
 }
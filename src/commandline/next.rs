@@ -0,0 +1,203 @@
+use crate::commandline::table;
+use crate::config::{Config, Urgency};
+use crate::database::dependency;
+use crate::database::task::{self, Task};
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use rusqlite::Connection;
+use std::error::Error;
+
+pub fn next(config: &Config, amount: u64, all: bool) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+    let now = Local::now().naive_local();
+
+    let (blocked, blocking) = dependency::get_blocked_and_blocking(&conn)?;
+    let mut ranked: Vec<(f64, Task)> = task::get_pending(&conn, all)?
+        .into_iter()
+        .map(|task| {
+            let urgency = urgency(
+                &task,
+                &config.urgency,
+                now,
+                blocked.contains(&task.id),
+                blocking.contains(&task.id),
+            );
+            (urgency, task)
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.id.cmp(&b.1.id)));
+    ranked.truncate(amount as usize);
+
+    let rows: Vec<Vec<String>> = ranked
+        .iter()
+        .map(|(urgency, task)| {
+            let mut dependencies = Vec::new();
+            if blocked.contains(&task.id) {
+                dependencies.push("blocked");
+            }
+            if blocking.contains(&task.id) {
+                dependencies.push("blocking");
+            }
+
+            vec![
+                task.id.to_string(),
+                format!("{:.2}", urgency),
+                task.priority.clone().unwrap_or_default(),
+                task.due.clone().unwrap_or_default(),
+                task.project.clone().unwrap_or_default(),
+                dependencies.join(", "),
+                task.name.clone(),
+            ]
+        })
+        .collect();
+
+    let header = [
+        "id",
+        "urgency",
+        "priority",
+        "due",
+        "project",
+        "dependencies",
+        "description",
+    ];
+    println!("Next {} tasks:", rows.len());
+    table::print(&header, &rows);
+
+    Ok(())
+}
+
+// Same weighting as taskwarrior: every term is scaled to 0..1 and multiplied by its coefficient
+pub fn urgency(
+    task: &Task,
+    coefficients: &Urgency,
+    now: NaiveDateTime,
+    blocked: bool,
+    blocking: bool,
+) -> f64 {
+    let mut urgency = 0.0;
+
+    if let Some(due) = task
+        .due
+        .as_deref()
+        .and_then(|due| NaiveDate::parse_from_str(due, "%Y-%m-%d").ok())
+    {
+        let due = due.and_hms_opt(0, 0, 0).unwrap();
+        let days_overdue = (now - due).num_seconds() as f64 / 86400.0;
+        let due_factor = if days_overdue >= 7.0 {
+            1.0
+        } else if days_overdue >= -14.0 {
+            (days_overdue + 14.0) * 0.8 / 21.0 + 0.2
+        } else {
+            0.2
+        };
+        urgency += due_factor * coefficients.due;
+    }
+
+    urgency += match task.priority.as_deref() {
+        Some("H") => coefficients.priority_h,
+        Some("M") => coefficients.priority_m,
+        Some("L") => coefficients.priority_l,
+        _ => 0.0,
+    };
+
+    if let Ok(entry) = NaiveDateTime::parse_from_str(&task.entry, "%Y-%m-%d %H:%M:%S") {
+        let age_days = (now - entry).num_seconds() as f64 / 86400.0;
+        urgency += (age_days / 365.0).clamp(0.0, 1.0) * coefficients.age;
+    }
+
+    if task.project.is_some() {
+        urgency += coefficients.project;
+    }
+    if blocking {
+        urgency += coefficients.blocking;
+    }
+    if blocked {
+        urgency += coefficients.blocked;
+    }
+
+    urgency
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(priority: Option<&str>, due: Option<&str>, project: Option<&str>) -> Task {
+        Task {
+            id: 1,
+            name: "test".to_string(),
+            priority: priority.map(String::from),
+            due: due.map(String::from),
+            entry: "2026-01-01 00:00:00".to_string(),
+            project: project.map(String::from),
+        }
+    }
+
+    fn now() -> NaiveDateTime {
+        NaiveDateTime::parse_from_str("2026-01-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_urgency_terms() {
+        let coefficients = Urgency::default();
+
+        assert_eq!(
+            urgency(&task(None, None, None), &coefficients, now(), false, false),
+            0.0
+        );
+        assert_eq!(
+            urgency(
+                &task(Some("H"), None, Some("work")),
+                &coefficients,
+                now(),
+                false,
+                false
+            ),
+            coefficients.priority_h + coefficients.project
+        );
+        assert_eq!(
+            urgency(
+                &task(None, Some("2025-12-01"), None),
+                &coefficients,
+                now(),
+                false,
+                false
+            ),
+            coefficients.due
+        );
+        assert_eq!(
+            urgency(
+                &task(None, Some("2027-01-01"), None),
+                &coefficients,
+                now(),
+                false,
+                false
+            ),
+            0.2 * coefficients.due
+        );
+        assert_eq!(
+            urgency(&task(None, None, None), &coefficients, now(), true, true),
+            coefficients.blocking + coefficients.blocked
+        );
+    }
+
+    #[test]
+    fn test_urgency_ordering() {
+        let coefficients = Urgency::default();
+        let overdue = urgency(
+            &task(None, Some("2025-12-20"), None),
+            &coefficients,
+            now(),
+            false,
+            false,
+        );
+        let later = urgency(
+            &task(None, Some("2026-01-10"), None),
+            &coefficients,
+            now(),
+            false,
+            false,
+        );
+
+        assert!(overdue > later);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::config::Config;
+use crate::database::project;
+use rusqlite::{params, Connection};
+use std::error::Error;
+
+pub fn show(config: &Config, name: &str) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    let id = match project::find_id(&conn, name)? {
+        Some(id) => id,
+        None => {
+            eprintln!("Project '{}' does not exist", name);
+            std::process::exit(1);
+        }
+    };
+
+    let (status, start, target): (String, Option<String>, Option<String>) = conn.query_row(
+        "SELECT status, start, target FROM project WHERE id = ?",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    // everything below is rolled up over the project and all of its sub-projects
+    let tree_ids = project::get_tree_ids(&conn, id)?;
+    let ids = project::join_ids(&tree_ids);
+
+    println!(
+        "Project {}: '{}' [{}]",
+        id,
+        project::get_path(&conn, id)?,
+        status
+    );
+    println!(
+        "  start: {}, target: {}",
+        start.unwrap_or("-".to_string()),
+        target.unwrap_or("-".to_string())
+    );
+    println!(
+        "  progress: {}",
+        format_progress(project::get_progress(&conn, &tree_ids)?)
+    );
+
+    println!("Sub-projects:");
+    let sub_projects = query_entries(
+        &conn,
+        &format!(
+            "SELECT id, name || ' [' || status || ']' FROM project WHERE parent_project_id = {}",
+            id
+        ),
+    )?;
+    for (sub_project_id, sub_project) in &sub_projects {
+        let progress =
+            project::get_progress(&conn, &project::get_tree_ids(&conn, *sub_project_id)?)?;
+        println!(
+            "  {}: {} {}",
+            sub_project_id,
+            sub_project,
+            format_progress(progress)
+        );
+    }
+    if sub_projects.is_empty() {
+        println!("  (none)");
+    }
+
+    print_entries(
+        &conn,
+        "Tasks",
+        &format!(
+            "SELECT DISTINCT task.id,
+                CASE WHEN task.status = 'done' THEN '[x] ' ELSE '[ ] ' END || task.name
+            FROM task
+            JOIN TaskHasProject ON TaskHasProject.task_id = task.id
+            WHERE TaskHasProject.project_id IN ({})
+            AND task.status IN ('pending', 'done')
+            ORDER BY task.id",
+            ids
+        ),
+    )?;
+    print_entries(
+        &conn,
+        "Notes",
+        &format!(
+            "SELECT id, content FROM note WHERE project_id IN ({}) ORDER BY id",
+            ids
+        ),
+    )?;
+    print_entries(
+        &conn,
+        "Ideas",
+        &format!(
+            "SELECT id, name FROM idea
+            WHERE project_id IN ({0})
+            OR id IN (SELECT idea_id FROM ProjectHasIdea WHERE project_id IN ({0}))
+            ORDER BY id",
+            ids
+        ),
+    )?;
+    print_entries(
+        &conn,
+        "Writings",
+        &format!(
+            "SELECT id, name FROM writing
+            WHERE project_id IN ({0})
+            OR id IN (SELECT writing_id FROM WritingHasProject WHERE project_id IN ({0}))
+            ORDER BY id",
+            ids
+        ),
+    )?;
+    print_entries(
+        &conn,
+        "Code",
+        &format!(
+            "SELECT id, name FROM code
+            WHERE project_id IN ({0})
+            OR id IN (SELECT code_id FROM CodeHasProject WHERE project_id IN ({0}))
+            ORDER BY id",
+            ids
+        ),
+    )?;
+
+    Ok(())
+}
+
+fn print_entries(conn: &Connection, title: &str, query: &str) -> Result<(), Box<dyn Error>> {
+    println!("{}:", title);
+
+    let entries = query_entries(conn, query)?;
+    for (id, content) in &entries {
+        println!("  {}: {}", id, content);
+    }
+    if entries.is_empty() {
+        println!("  (none)");
+    }
+
+    Ok(())
+}
+
+fn query_entries(conn: &Connection, query: &str) -> Result<Vec<(i64, String)>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(query)?;
+    let entries: Vec<(i64, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(entries)
+}
+
+fn format_progress((done, total): (i64, i64)) -> String {
+    let percentage = if total == 0 { 0 } else { done * 100 / total };
+    format!("{}/{} tasks done ({}%)", done, total, percentage)
+}
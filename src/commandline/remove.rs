@@ -4,15 +4,17 @@ use rusqlite::Connection;
 use std::error::Error;
 
 pub fn rm(config: &Config, entry_type: &str, id: u64) -> Result<(), Box<dyn Error>> {
-    let conn = Connection::open(&config.db_path)?;
+    let mut conn = Connection::open(&config.db_path)?;
 
-    let _ = match entry_type {
-        "note" => note::remove(&config.note_path, &conn, id),
-        "idea" => idea::remove(&conn, id),
-        "task" => task::remove(&conn, id),
-        "project" => project::remove(&conn, id),
-        "writings" => writing::remove(&conn, id),
-        "code" => code::remove(&conn, id),
+    // an entry and its links are removed together, a failure part-way leaves both in place
+    let transaction = conn.transaction()?;
+    let result = match entry_type {
+        "note" => note::remove(&config.note_path, &transaction, id),
+        "idea" => idea::remove(&transaction, id),
+        "task" => task::remove(&transaction, id),
+        "project" => project::remove(&transaction, id),
+        "writings" => writing::remove(&transaction, id),
+        "code" => code::remove(&transaction, id),
         _ => {
             eprintln!(
                 "Invalid entry type. Use 'note', 'idea', 'task', 'project', 'writings', or 'code'."
@@ -20,6 +22,9 @@ pub fn rm(config: &Config, entry_type: &str, id: u64) -> Result<(), Box<dyn Erro
             std::process::exit(1);
         }
     };
+    if result.is_ok() {
+        transaction.commit()?;
+    }
 
-    Ok(())
+    result
 }
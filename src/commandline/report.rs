@@ -0,0 +1,264 @@
+use crate::commandline::table;
+use crate::config::Config;
+use crate::database::project;
+use chrono::{Datelike, Days, Local, NaiveDate, NaiveDateTime};
+use rusqlite::Connection;
+use std::error::Error;
+
+const BAR_WIDTH: usize = 40;
+// number of recent weeks the velocity is averaged over
+const VELOCITY_WEEKS: usize = 4;
+
+pub struct TaskSpan {
+    pub entry: NaiveDateTime,
+    pub end: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Week {
+    pub monday: NaiveDate,
+    pub open: usize,
+    pub done: usize,
+    pub completed: usize,
+}
+
+pub fn burndown(config: &Config, name: &str) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    let id = match project::find_id(&conn, name)? {
+        Some(id) => id,
+        None => {
+            eprintln!("Project '{}' does not exist", name);
+            std::process::exit(1);
+        }
+    };
+
+    let tasks = get_task_spans(&conn, &project::get_tree_ids(&conn, id)?)?;
+    if tasks.is_empty() {
+        println!("Project '{}' has no tasks", project::get_path(&conn, id)?);
+        return Ok(());
+    }
+
+    let today = Local::now().date_naive();
+    let weeks = get_weeks(&tasks, today);
+
+    println!(
+        "Burndown of project {}: '{}' (# open, = done)",
+        id,
+        project::get_path(&conn, id)?
+    );
+    let max = weeks
+        .iter()
+        .map(|week| week.open + week.done)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    for week in &weeks {
+        let open = week.open * BAR_WIDTH / max;
+        let done = week.done * BAR_WIDTH / max;
+        println!(
+            "{} |{}{}{} {} open, {} done",
+            week.monday.format("%Y-%m-%d"),
+            "#".repeat(open),
+            "=".repeat(done),
+            " ".repeat(BAR_WIDTH - open - done),
+            week.open,
+            week.done
+        );
+    }
+
+    println!();
+    let rows: Vec<Vec<String>> = weeks
+        .iter()
+        .rev()
+        .take(VELOCITY_WEEKS)
+        .rev()
+        .map(|week| {
+            vec![
+                week.monday.format("%Y-%m-%d").to_string(),
+                week.completed.to_string(),
+            ]
+        })
+        .collect();
+    table::print(&["week", "completed"], &rows);
+
+    println!();
+    let velocity = velocity(&weeks);
+    let open = weeks.last().map_or(0, |week| week.open);
+    println!("Velocity: {:.2} tasks per week", velocity);
+    match estimate_finish(open, velocity, today) {
+        Some(date) if open > 0 => println!(
+            "{} open tasks, estimated to be finished around {}",
+            open,
+            date.format("%Y-%m-%d")
+        ),
+        Some(_) => println!("All tasks are done"),
+        None => println!(
+            "{} open tasks, no estimate without completed tasks in the last {} weeks",
+            open, VELOCITY_WEEKS
+        ),
+    }
+
+    Ok(())
+}
+
+// Deleted tasks and recurring templates don't count towards the work of a project
+fn get_task_spans(conn: &Connection, ids: &[i64]) -> Result<Vec<TaskSpan>, Box<dyn Error>> {
+    let query = format!(
+        "SELECT DISTINCT task.id, task.entry, CASE WHEN task.status = 'done' THEN task.end END
+        FROM task
+        JOIN TaskHasProject ON TaskHasProject.task_id = task.id
+        WHERE TaskHasProject.project_id IN ({})
+        AND task.status IN ('pending', 'done')",
+        project::join_ids(ids)
+    );
+    let mut stmt = conn.prepare(&query)?;
+    let tasks: Vec<TaskSpan> = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<usize, String>(1)?,
+                row.get::<usize, Option<String>>(2)?,
+            ))
+        })?
+        .filter_map(Result::ok)
+        .filter_map(|(entry, end)| {
+            Some(TaskSpan {
+                entry: parse_timestamp(&entry)?,
+                end: end.as_deref().and_then(parse_timestamp),
+            })
+        })
+        .collect();
+
+    Ok(tasks)
+}
+
+// One entry per week from the week the first task was added up to the current week, counted at
+// the end of each week
+pub fn get_weeks(tasks: &[TaskSpan], today: NaiveDate) -> Vec<Week> {
+    let first = match tasks.iter().map(|task| task.entry.date()).min() {
+        Some(first) => get_monday(first),
+        None => return Vec::new(),
+    };
+    let last = get_monday(today);
+
+    let mut weeks = Vec::new();
+    let mut monday = first;
+    while monday <= last {
+        let end = (monday + Days::new(7)).and_hms_opt(0, 0, 0).unwrap();
+        let start = monday.and_hms_opt(0, 0, 0).unwrap();
+
+        let added: Vec<&TaskSpan> = tasks.iter().filter(|task| task.entry < end).collect();
+        let done = added
+            .iter()
+            .filter(|task| task.end.is_some_and(|task_end| task_end < end))
+            .count();
+        let completed = added
+            .iter()
+            .filter(|task| {
+                task.end
+                    .is_some_and(|task_end| task_end >= start && task_end < end)
+            })
+            .count();
+
+        weeks.push(Week {
+            monday,
+            open: added.len() - done,
+            done,
+            completed,
+        });
+        monday = monday + Days::new(7);
+    }
+
+    weeks
+}
+
+// Average of completed tasks per week over the last few weeks
+pub fn velocity(weeks: &[Week]) -> f64 {
+    let recent: Vec<&Week> = weeks.iter().rev().take(VELOCITY_WEEKS).collect();
+    if recent.is_empty() {
+        return 0.0;
+    }
+
+    recent.iter().map(|week| week.completed).sum::<usize>() as f64 / recent.len() as f64
+}
+
+pub fn estimate_finish(open: usize, velocity: f64, today: NaiveDate) -> Option<NaiveDate> {
+    if open == 0 {
+        return Some(today);
+    }
+    if velocity <= 0.0 {
+        return None;
+    }
+
+    let days = (open as f64 / velocity * 7.0).ceil() as u64;
+    Some(today + Days::new(days))
+}
+
+fn get_monday(date: NaiveDate) -> NaiveDate {
+    date - Days::new(date.weekday().num_days_from_monday().into())
+}
+
+fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(entry: &str, end: Option<&str>) -> TaskSpan {
+        TaskSpan {
+            entry: parse_timestamp(entry).unwrap(),
+            end: end.map(|end| parse_timestamp(end).unwrap()),
+        }
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_get_weeks() {
+        // 2026-10-05 is a monday
+        let tasks = [
+            span("2026-10-05 09:00:00", Some("2026-10-07 12:00:00")),
+            span("2026-10-06 09:00:00", Some("2026-10-14 12:00:00")),
+            span("2026-10-13 09:00:00", None),
+        ];
+        let weeks = get_weeks(&tasks, date("2026-10-21"));
+
+        assert_eq!(
+            weeks,
+            vec![
+                Week {
+                    monday: date("2026-10-05"),
+                    open: 1,
+                    done: 1,
+                    completed: 1,
+                },
+                Week {
+                    monday: date("2026-10-12"),
+                    open: 1,
+                    done: 2,
+                    completed: 1,
+                },
+                Week {
+                    monday: date("2026-10-19"),
+                    open: 1,
+                    done: 2,
+                    completed: 0,
+                },
+            ]
+        );
+        assert!((velocity(&weeks) - 2.0 / 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_estimate_finish() {
+        let today = date("2026-10-19");
+
+        assert_eq!(estimate_finish(4, 2.0, today), Some(date("2026-11-02")));
+        assert_eq!(estimate_finish(0, 0.0, today), Some(today));
+        assert_eq!(estimate_finish(3, 0.0, today), None);
+    }
+}
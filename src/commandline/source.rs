@@ -0,0 +1,93 @@
+use crate::commandline::table;
+use crate::config::Config;
+use crate::database::source;
+use rusqlite::Connection;
+use std::error::Error;
+
+pub fn list(config: &Config) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    let rows: Vec<Vec<String>> = source::list(&conn)?
+        .into_iter()
+        .map(|(id, name, key, kind, year, notes, attachments)| {
+            vec![
+                id.to_string(),
+                name,
+                key.unwrap_or_default(),
+                kind.unwrap_or_default(),
+                year.unwrap_or_default(),
+                notes.to_string(),
+                attachments.to_string(),
+            ]
+        })
+        .collect();
+    if rows.is_empty() {
+        println!("No sources yet");
+        return Ok(());
+    }
+
+    table::print(
+        &["id", "name", "key", "type", "year", "notes", "attachments"],
+        &rows,
+    );
+
+    Ok(())
+}
+
+pub fn rename(config: &Config, value: &str, name: &str) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    let id = get_id(&conn, value)?;
+    let old_name = source::get_name(&conn, id)?;
+    source::rename(&conn, id, name)?;
+
+    println!("Renamed source {}: '{}' to '{}'", id, old_name, name);
+    Ok(())
+}
+
+pub fn merge(config: &Config, from: &str, into: &str) -> Result<(), Box<dyn Error>> {
+    let mut conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    let from_id = get_id(&conn, from)?;
+    let into_id = get_id(&conn, into)?;
+    let from_name = source::get_name(&conn, from_id)?;
+    let into_name = source::get_name(&conn, into_id)?;
+
+    let mut input = String::new();
+    println!(
+        "Are you sure you want to merge source '{}' into '{}' and delete it? (y/n)",
+        from_name, into_name
+    );
+    std::io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+
+    if input.trim() != "y" {
+        println!("Aborting merge");
+        return Ok(());
+    }
+
+    // a failed merge must not leave notes pointing to a deleted source
+    let transaction = conn.transaction()?;
+    if let Err(err) = source::merge(&transaction, from_id, into_id) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+    transaction.commit()?;
+
+    println!(
+        "Merged source {}: '{}' into source {}: '{}'",
+        from_id, from_name, into_id, into_name
+    );
+    Ok(())
+}
+
+fn get_id(conn: &Connection, value: &str) -> Result<i64, Box<dyn Error>> {
+    match source::find_id(conn, value)? {
+        Some(id) => Ok(id),
+        None => {
+            eprintln!("Source '{}' does not exist", value);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use crate::config::Config;
+use crate::database;
+use rusqlite::Connection;
+use std::error::Error;
+
+pub fn sync(config: &Config, sync_type: &str) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    match sync_type {
+        "todos" => {
+            // read the files first, so changes made in the editor are not overwritten
+            database::todo::import(&conn, &config.todo_path)?;
+            database::todo::render(&conn, &config.todo_path)?;
+            println!("Synced todos in {}", config.todo_path.display());
+        }
+        _ => {
+            eprintln!("Invalid sync type. Use 'todos'.");
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
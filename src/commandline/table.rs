@@ -0,0 +1,34 @@
+use std::cmp;
+
+// Prints rows as a Markdown-like table with every column as wide as its longest value
+pub fn print(header: &[&str], rows: &[Vec<String>]) {
+    let mut max_widths: Vec<usize> = header.iter().map(|column| column.len()).collect();
+    for row in rows {
+        for (i, value) in row.iter().enumerate() {
+            max_widths[i] = cmp::max(max_widths[i], value.chars().count());
+        }
+    }
+
+    let header: Vec<String> = header.iter().map(|column| column.to_string()).collect();
+    print_row(&header, &max_widths);
+    println!(
+        "|{}|",
+        max_widths
+            .iter()
+            .map(|width| "-".repeat(width + 2))
+            .collect::<Vec<String>>()
+            .join("|")
+    );
+    for row in rows {
+        print_row(row, &max_widths);
+    }
+}
+
+fn print_row(row: &[String], max_widths: &[usize]) {
+    let cells: Vec<String> = row
+        .iter()
+        .zip(max_widths)
+        .map(|(value, width)| format!(" {:<width$} ", value, width = width))
+        .collect();
+    println!("|{}|", cells.join("|"));
+}
@@ -0,0 +1,158 @@
+use crate::commandline::table;
+use crate::config::Config;
+use crate::database::{project, timelog};
+use chrono::{Datelike, Days, Local, NaiveDateTime};
+use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+
+pub fn start(config: &Config, task_id: u64) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+    timelog::start(&conn, task_id)
+}
+
+pub fn stop(config: &Config) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+    if !timelog::stop(&conn)? {
+        println!("No task is running");
+    }
+
+    Ok(())
+}
+
+pub fn timesheet(config: &Config, week: bool) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+    let now = Local::now().naive_local();
+
+    // the week runs from monday to monday, without --week everything ever tracked is counted
+    let (from, to) = if week {
+        let today = now.date();
+        let monday = today - Days::new(today.weekday().num_days_from_monday().into());
+        let monday = monday.and_hms_opt(0, 0, 0).unwrap();
+        (Some(monday), Some(monday + Days::new(7)))
+    } else {
+        (None, None)
+    };
+
+    let mut project_hours: HashMap<Option<i64>, f64> = HashMap::new();
+    let mut topics: BTreeMap<String, f64> = BTreeMap::new();
+    let mut total = 0.0;
+
+    for entry in timelog::get_entries(&conn)? {
+        let hours = clipped_hours(entry.start, entry.end.unwrap_or(now), from, to);
+        if hours <= 0.0 {
+            continue;
+        }
+        total += hours;
+
+        let project_topics = match entry.project_id {
+            Some(project_id) => get_topics(&conn, project_id)?,
+            None => Vec::new(),
+        };
+
+        *project_hours.entry(entry.project_id).or_default() += hours;
+        if project_topics.is_empty() {
+            *topics.entry("(none)".to_string()).or_default() += hours;
+        }
+        for topic in project_topics {
+            *topics.entry(topic).or_default() += hours;
+        }
+    }
+
+    // the hours of a project include the ones of its sub-projects
+    let mut projects: BTreeMap<String, f64> = BTreeMap::new();
+    let mut stmt = conn.prepare("SELECT id FROM project")?;
+    let project_ids: Vec<i64> = stmt
+        .query_map([], |row| row.get(0))?
+        .filter_map(Result::ok)
+        .collect();
+    for project_id in project_ids {
+        let hours: f64 = project::get_tree_ids(&conn, project_id)?
+            .iter()
+            .filter_map(|id| project_hours.get(&Some(*id)))
+            .sum();
+        if hours > 0.0 {
+            projects.insert(project::get_path(&conn, project_id)?, hours);
+        }
+    }
+    if let Some(hours) = project_hours.get(&None) {
+        projects.insert("(none)".to_string(), *hours);
+    }
+
+    match (from, to) {
+        (Some(from), Some(to)) => println!(
+            "Timesheet from {} to {}:",
+            from.format("%Y-%m-%d"),
+            (to - Days::new(1)).format("%Y-%m-%d")
+        ),
+        _ => println!("Timesheet:"),
+    }
+
+    let rows: Vec<Vec<String>> = projects
+        .iter()
+        .map(|(project, hours)| vec![project.clone(), format!("{:.2}", hours)])
+        .collect();
+    table::print(&["project", "hours"], &rows);
+
+    println!();
+    let rows: Vec<Vec<String>> = topics
+        .iter()
+        .map(|(topic, hours)| vec![topic.clone(), format!("{:.2}", hours)])
+        .collect();
+    table::print(&["topic", "hours"], &rows);
+
+    println!();
+    println!("Total: {:.2} hours", total);
+
+    Ok(())
+}
+
+fn clipped_hours(
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+) -> f64 {
+    let start = from.map_or(start, |from| start.max(from));
+    let end = to.map_or(end, |to| end.min(to));
+
+    (end - start).num_seconds() as f64 / 3600.0
+}
+
+fn get_topics(conn: &Connection, project_id: i64) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT topic.name FROM topic
+        JOIN ProjectHasTopic ON ProjectHasTopic.topic_id = topic.id
+        WHERE ProjectHasTopic.project_id = ?",
+    )?;
+    let topics: Vec<String> = stmt
+        .query_map(params![project_id], |row| row.get(0))?
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(topics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_clipped_hours() {
+        let at = |day: u32, hour: u32| {
+            NaiveDate::from_ymd_opt(2026, 3, day)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap()
+        };
+
+        assert_eq!(clipped_hours(at(2, 9), at(2, 12), None, None), 3.0);
+        // an entry running over midnight only counts until the end of the range
+        assert_eq!(
+            clipped_hours(at(8, 22), at(9, 2), Some(at(2, 0)), Some(at(9, 0))),
+            2.0
+        );
+        assert!(clipped_hours(at(1, 9), at(1, 12), Some(at(2, 0)), Some(at(9, 0))) <= 0.0);
+    }
+}
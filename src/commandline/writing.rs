@@ -0,0 +1,331 @@
+use crate::commandline::table;
+use crate::config::Config;
+use crate::database::writing::{self, AttachedNote};
+use chrono::{Days, Local, NaiveDate};
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// number of days listed in the words per day table
+const DAYS: u64 = 14;
+
+// word count of a writing per recorded day
+type Snapshots = Vec<(NaiveDate, i64)>;
+
+struct Writing {
+    id: i64,
+    name: String,
+    kind: String,
+    words: i64,
+    goal: Option<i64>,
+    daily_goal: Option<i64>,
+}
+
+pub fn stats(config: &Config) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+    writing::refresh(&conn)?;
+
+    let today = Local::now().date_naive();
+    let writings = get_writings(&conn)?;
+    let snapshots = get_snapshots(&conn)?;
+
+    let mut totals: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+    let mut rows = Vec::new();
+    let mut journal_words = 0;
+    let mut journal_days = Vec::new();
+
+    for writing in &writings {
+        // journal entries are registered once they are written, so they count from zero
+        let daily = daily_words(
+            snapshots.get(&writing.id).map_or(&[], Vec::as_slice),
+            writing.kind != "journal",
+        );
+        for (date, words) in &daily {
+            *totals.entry(*date).or_default() += words;
+        }
+
+        // journal entries are summed up instead of listed one by one
+        if writing.kind == "journal" {
+            journal_words += writing.words;
+            journal_days.extend(daily.into_iter().filter(|(_, words)| *words > 0));
+            continue;
+        }
+
+        let written_today = daily
+            .iter()
+            .find(|(date, _)| *date == today)
+            .map_or(0, |(_, words)| *words);
+        rows.push(vec![
+            writing.id.to_string(),
+            writing.name.clone(),
+            writing.words.to_string(),
+            format_goal(writing.words, writing.goal),
+            format_goal(written_today, writing.daily_goal),
+            streak(&daily, writing.daily_goal.unwrap_or(1), today).to_string(),
+        ]);
+    }
+
+    println!("Writings:");
+    table::print(&["id", "name", "words", "goal", "today", "streak"], &rows);
+
+    println!();
+    println!(
+        "Journal: {} words, {} day streak",
+        journal_words,
+        streak(&journal_days, 1, today)
+    );
+
+    println!();
+    let rows: Vec<Vec<String>> = (0..DAYS)
+        .rev()
+        .map(|days| today - Days::new(days))
+        .map(|date| {
+            vec![
+                date.format("%Y-%m-%d").to_string(),
+                totals.get(&date).copied().unwrap_or(0).to_string(),
+            ]
+        })
+        .collect();
+    println!("Words per day:");
+    table::print(&["date", "words"], &rows);
+
+    Ok(())
+}
+
+// Entries are given as note:17 note:42 and keep their order
+pub fn attach(config: &Config, id: u64, entries: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    let mut note_ids = Vec::new();
+    for entry in entries {
+        match entry
+            .strip_prefix("note:")
+            .and_then(|note_id| note_id.parse::<i64>().ok())
+        {
+            Some(note_id) => note_ids.push(note_id),
+            None => {
+                eprintln!("Invalid entry '{}'. Use note:<id>.", entry);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let transaction = conn.transaction()?;
+    writing::attach(&transaction, id, &note_ids)?;
+    transaction.commit()?;
+
+    Ok(())
+}
+
+pub fn compile(
+    config: &Config,
+    id: u64,
+    output: &Path,
+    headings: bool,
+) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open(&config.db_path).expect("Failed to open database");
+
+    let (name, body, notes) = writing::get_manuscript(&conn, id)?;
+    fs::write(output, compile_manuscript(&body, &notes, headings))?;
+
+    println!(
+        "Compiled writing {}: '{}' with {} notes to {}",
+        id,
+        name,
+        notes.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+// The writing body comes first, followed by the notes in order and the sources of all notes
+fn compile_manuscript(body: &str, notes: &[AttachedNote], headings: bool) -> String {
+    let mut sections = Vec::new();
+    if !body.trim().is_empty() {
+        sections.push(body.trim().to_string());
+    }
+
+    // consecutive notes of the same topic share one heading
+    let mut topic = "";
+    for note in notes {
+        if headings && !note.topic.is_empty() && note.topic != topic {
+            sections.push(format!("## {}\n\n{}", note.topic, note.content.trim()));
+        } else {
+            sections.push(note.content.trim().to_string());
+        }
+        topic = &note.topic;
+    }
+
+    let mut sources: Vec<&String> = notes.iter().flat_map(|note| &note.sources).collect();
+    sources.sort();
+    sources.dedup();
+    if !sources.is_empty() {
+        let entries: Vec<String> = sources
+            .iter()
+            .map(|source| format!("- {}", source))
+            .collect();
+        sections.push(format!("## Bibliography\n\n{}", entries.join("\n")));
+    }
+
+    format!("{}\n", sections.join("\n\n"))
+}
+
+fn get_writings(conn: &Connection) -> Result<Vec<Writing>, Box<dyn Error>> {
+    let mut stmt =
+        conn.prepare("SELECT id, name, kind, words, goal, daily_goal FROM writing ORDER BY id")?;
+    let writings: Vec<Writing> = stmt
+        .query_map([], |row| {
+            Ok(Writing {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                kind: row.get(2)?,
+                words: row.get(3)?,
+                goal: row.get(4)?,
+                daily_goal: row.get(5)?,
+            })
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(writings)
+}
+
+fn get_snapshots(conn: &Connection) -> Result<BTreeMap<i64, Snapshots>, Box<dyn Error>> {
+    let mut stmt =
+        conn.prepare("SELECT writing_id, date, words FROM WritingSnapshot ORDER BY date")?;
+    let rows: Vec<(i64, String, i64)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .filter_map(Result::ok)
+        .collect();
+
+    let mut snapshots: BTreeMap<i64, Snapshots> = BTreeMap::new();
+    for (writing_id, date, words) in rows {
+        if let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+            snapshots.entry(writing_id).or_default().push((date, words));
+        }
+    }
+
+    Ok(snapshots)
+}
+
+// Words written on each recorded day, deleting text doesn't count against the day. With a
+// baseline, the first snapshot only holds the words the writing started with.
+pub fn daily_words(snapshots: &[(NaiveDate, i64)], baseline: bool) -> Vec<(NaiveDate, i64)> {
+    let mut previous = match snapshots.first() {
+        Some((_, words)) if baseline => *words,
+        _ => 0,
+    };
+
+    snapshots
+        .iter()
+        .map(|(date, words)| {
+            let written = (words - previous).max(0);
+            previous = *words;
+            (*date, written)
+        })
+        .collect()
+}
+
+// Consecutive days up to today on which the goal was met. A streak isn't broken before the day
+// is over, so it may also end yesterday.
+pub fn streak(daily: &[(NaiveDate, i64)], goal: i64, today: NaiveDate) -> u64 {
+    let met = |date: NaiveDate| {
+        daily
+            .iter()
+            .any(|(day, words)| *day == date && *words >= goal)
+    };
+
+    let mut date = if met(today) {
+        today
+    } else {
+        today - Days::new(1)
+    };
+    let mut streak = 0;
+    while met(date) {
+        streak += 1;
+        date = date - Days::new(1);
+    }
+
+    streak
+}
+
+fn format_goal(words: i64, goal: Option<i64>) -> String {
+    match goal {
+        Some(goal) => format!("{}/{} ({}%)", words, goal, words * 100 / goal),
+        None => words.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_compile_manuscript() {
+        let notes = [
+            AttachedNote {
+                id: 17,
+                content: "Gravity bends light.".to_string(),
+                topic: "physics".to_string(),
+                sources: vec!["Cosmos".to_string()],
+            },
+            AttachedNote {
+                id: 42,
+                content: "Light is fast.\n".to_string(),
+                topic: String::new(),
+                sources: vec!["Cosmos".to_string(), "Lectures".to_string()],
+            },
+        ];
+
+        assert_eq!(
+            compile_manuscript("# Light\n", &notes, true),
+            "# Light\n\n## physics\n\nGravity bends light.\n\nLight is fast.\n\n\
+            ## Bibliography\n\n- Cosmos\n- Lectures\n"
+        );
+        assert_eq!(
+            compile_manuscript("", &notes[..1], false),
+            "Gravity bends light.\n\n## Bibliography\n\n- Cosmos\n"
+        );
+    }
+
+    #[test]
+    fn test_daily_words() {
+        let snapshots = [
+            (date("2026-10-14"), 200),
+            (date("2026-10-15"), 150),
+            (date("2026-10-17"), 600),
+        ];
+
+        assert_eq!(
+            daily_words(&snapshots, false),
+            vec![
+                (date("2026-10-14"), 200),
+                (date("2026-10-15"), 0),
+                (date("2026-10-17"), 450),
+            ]
+        );
+        assert_eq!(daily_words(&snapshots, true)[0], (date("2026-10-14"), 0));
+    }
+
+    #[test]
+    fn test_streak() {
+        let daily = [
+            (date("2026-10-14"), 600),
+            (date("2026-10-15"), 100),
+            (date("2026-10-16"), 500),
+            (date("2026-10-17"), 700),
+        ];
+
+        assert_eq!(streak(&daily, 500, date("2026-10-17")), 2);
+        // today isn't over yet, so yesterday still counts
+        assert_eq!(streak(&daily, 500, date("2026-10-18")), 2);
+        assert_eq!(streak(&daily, 500, date("2026-10-19")), 0);
+        assert_eq!(streak(&daily, 1, date("2026-10-17")), 4);
+    }
+}
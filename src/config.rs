@@ -15,9 +15,38 @@ pub struct Config {
     pub code_path: PathBuf,
     pub theme: String,
     pub editor: String,
+    pub urgency: Urgency,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Urgency {
+    pub due: f64,
+    pub priority_h: f64,
+    pub priority_m: f64,
+    pub priority_l: f64,
+    pub age: f64,
+    pub project: f64,
+    pub blocking: f64,
+    pub blocked: f64,
+}
+
+impl Default for Urgency {
+    fn default() -> Urgency {
+        Urgency {
+            due: 12.0,
+            priority_h: 6.0,
+            priority_m: 3.9,
+            priority_l: 1.8,
+            age: 2.0,
+            project: 1.0,
+            blocking: 8.0,
+            blocked: -5.0,
+        }
+    }
 }
 
 impl Config {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         db_path: PathBuf,
         log_path: PathBuf,
@@ -39,6 +68,7 @@ impl Config {
             code_path,
             theme,
             editor,
+            urgency: Urgency::default(),
         }
     }
 
@@ -52,13 +82,15 @@ impl Config {
             note_path: home_dir.join("cerebra/notes"),
             misc_path: home_dir.join("cerebra/misc"),
             todo_path: home_dir.join("cerebra/todos"),
-            journal_path: home_dir.join("cerebra/code"),
+            journal_path: home_dir.join("cerebra/writings"),
             code_path: home_dir.join("cerebra/code"),
             theme: "dark".to_string(),
             editor: "nvim".to_string(),
+            urgency: Urgency::default(),
         }
     }
 
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         format!(
             r#"db_path={}
@@ -70,6 +102,14 @@ journal_path={}
 code_path={}
 theme={}
 editor={}
+urgency_due={}
+urgency_priority_h={}
+urgency_priority_m={}
+urgency_priority_l={}
+urgency_age={}
+urgency_project={}
+urgency_blocking={}
+urgency_blocked={}
 "#,
             self.db_path.to_str().unwrap(),
             self.log_path.to_str().unwrap(),
@@ -80,6 +120,14 @@ editor={}
             self.code_path.to_str().unwrap(),
             self.theme,
             self.editor,
+            self.urgency.due,
+            self.urgency.priority_h,
+            self.urgency.priority_m,
+            self.urgency.priority_l,
+            self.urgency.age,
+            self.urgency.project,
+            self.urgency.blocking,
+            self.urgency.blocked,
         )
     }
 
@@ -101,6 +149,14 @@ editor={}
                 "code_path" => config.code_path = PathBuf::from(value),
                 "theme" => config.theme = value.to_string(),
                 "editor" => config.editor = value.to_string(),
+                "urgency_due" => config.urgency.due = parse_coefficient(key, value),
+                "urgency_priority_h" => config.urgency.priority_h = parse_coefficient(key, value),
+                "urgency_priority_m" => config.urgency.priority_m = parse_coefficient(key, value),
+                "urgency_priority_l" => config.urgency.priority_l = parse_coefficient(key, value),
+                "urgency_age" => config.urgency.age = parse_coefficient(key, value),
+                "urgency_project" => config.urgency.project = parse_coefficient(key, value),
+                "urgency_blocking" => config.urgency.blocking = parse_coefficient(key, value),
+                "urgency_blocked" => config.urgency.blocked = parse_coefficient(key, value),
                 _ => panic!("Invalid key in config file"),
             }
         }
@@ -109,6 +165,13 @@ editor={}
     }
 }
 
+fn parse_coefficient(key: &str, value: &str) -> f64 {
+    value
+        .trim()
+        .parse()
+        .unwrap_or_else(|_| panic!("Invalid value for {} in config file", key))
+}
+
 pub fn get_config_path() -> Option<PathBuf> {
     let home = std::env::var("HOME").expect("Could not get home directory");
     let home_dir = PathBuf::from(home);
@@ -120,7 +183,7 @@ pub fn get_config_path() -> Option<PathBuf> {
         current_dir.join(".cerebra.conf"),
     ];
 
-    Some(config_paths.iter().find(|&path| path.exists()).cloned()?)
+    config_paths.iter().find(|&path| path.exists()).cloned()
 }
 
 pub fn create_config(config_path: &Path, config: &Config) -> Result<(), Box<dyn Error>> {
@@ -159,7 +222,7 @@ pub fn create_config(config_path: &Path, config: &Config) -> Result<(), Box<dyn
 }
 
 pub fn read_config(config_path: &Path) -> Result<Config, Box<dyn Error>> {
-    let config_str = std::fs::read_to_string(&config_path).expect("Could not read config file");
+    let config_str = std::fs::read_to_string(config_path).expect("Could not read config file");
 
     Ok(Config::from_string(&config_str))
 }
@@ -0,0 +1,244 @@
+use crate::database;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+// extension and MIME type of the files that are usually attached to sources
+const MIME_TYPES: [(&str, &str); 22] = [
+    ("pdf", "application/pdf"),
+    ("epub", "application/epub+zip"),
+    ("zip", "application/zip"),
+    ("json", "application/json"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("svg", "image/svg+xml"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+    ("mkv", "video/x-matroska"),
+    ("mov", "video/quicktime"),
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("wav", "audio/wav"),
+    ("flac", "audio/flac"),
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("html", "text/html"),
+    ("csv", "text/csv"),
+];
+
+// Files are stored as misc_path/<first two characters of the hash>/<hash>.<extension>, so the
+// same file is only stored once, however often it is attached
+pub fn add(
+    misc_directory: &Path,
+    conn: &Connection,
+    file: &Path,
+    tags: HashMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    let mut source = String::new();
+    let mut note: Option<i64> = None;
+
+    for (key, value) in &tags {
+        match key.as_str() {
+            "source" => source = value.to_string(),
+            "note" => {
+                note = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid note id '{}'", value))?,
+                )
+            }
+            _ => {
+                eprintln!("Invalid tag '{}'. Use 'source' or 'note'.", key);
+                std::process::exit(1);
+            }
+        }
+    }
+    if source.is_empty() {
+        eprintln!("Missing source. Use source:<name>.");
+        std::process::exit(1);
+    }
+
+    // checked before the file is copied, so a typo doesn't leave anything behind
+    let source_id = match database::source::find_id(conn, &source)? {
+        Some(source_id) => source_id,
+        None => {
+            eprintln!(
+                "Source '{}' does not exist. See `cerebra source list` for the sources.",
+                source
+            );
+            std::process::exit(1);
+        }
+    };
+    if let Some(note_id) = note {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM note WHERE id = ?)",
+            params![note_id],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            eprintln!("Note {} does not exist", note_id);
+            std::process::exit(1);
+        }
+    }
+
+    let hash = get_hash(file)?;
+    let size = fs::metadata(file)?.len();
+    let mime = get_mime_type(file)?;
+    let original_name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let path = get_store_path(misc_directory, &hash, file);
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(file, &path)?;
+    }
+
+    let existing_id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM attachment WHERE source_id = ? AND hash = ?",
+            params![source_id, hash],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let id = match existing_id {
+        Some(id) => {
+            println!(
+                "'{}' is already attached to source '{}' as attachment {}",
+                original_name, source, id
+            );
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO attachment (source_id, path, original_name, size, mime, hash, added)
+                VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    source_id,
+                    path.to_string_lossy(),
+                    original_name,
+                    size as i64,
+                    mime,
+                    hash,
+                    database::init::get_timestamp()
+                ],
+            )?;
+            let id = conn.last_insert_rowid();
+            println!(
+                "Added attachment {}: '{}' ({}, {} bytes) to source '{}'",
+                id, original_name, mime, size, source
+            );
+            id
+        }
+    };
+
+    if let Some(note_id) = note {
+        link_note(conn, note_id, source_id)?;
+        println!("Linked attachment {} to note {}", id, note_id);
+    }
+
+    Ok(())
+}
+
+// A link that was only there because of a citation is kept now, even once the citation is gone
+fn link_note(conn: &Connection, note_id: i64, source_id: i64) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "UPDATE NoteHasSource SET cited = 0 WHERE note_id = ? AND source_id = ?",
+        params![note_id, source_id],
+    )?;
+    conn.execute(
+        "INSERT INTO NoteHasSource (note_id, source_id)
+        SELECT ?1, ?2 WHERE NOT EXISTS (
+            SELECT 1 FROM NoteHasSource WHERE note_id = ?1 AND source_id = ?2
+        )",
+        params![note_id, source_id],
+    )?;
+
+    Ok(())
+}
+
+// Reads the file in chunks, attachments may be large videos
+fn get_hash(file: &Path) -> Result<String, Box<dyn Error>> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(file)?, &mut hasher)?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn get_store_path(misc_directory: &Path, hash: &str, file: &Path) -> PathBuf {
+    let name = match file.extension() {
+        Some(extension) => format!("{}.{}", hash, extension.to_string_lossy().to_lowercase()),
+        None => hash.to_string(),
+    };
+
+    misc_directory.join(&hash[..2]).join(name)
+}
+
+fn get_mime_type(file: &Path) -> Result<String, Box<dyn Error>> {
+    let mut header = Vec::new();
+    File::open(file)?.take(16).read_to_end(&mut header)?;
+
+    let extension = file
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    Ok(detect_mime_type(&extension, &header).to_string())
+}
+
+// The first bytes of the file win over its extension
+fn detect_mime_type(extension: &str, header: &[u8]) -> &'static str {
+    let signatures: [(&[u8], &str); 5] = [
+        (b"%PDF", "application/pdf"),
+        (b"\x89PNG", "image/png"),
+        (b"\xFF\xD8\xFF", "image/jpeg"),
+        (b"GIF8", "image/gif"),
+        (b"fLaC", "audio/flac"),
+    ];
+    if let Some((_, mime)) = signatures
+        .iter()
+        .find(|(signature, _)| header.starts_with(signature))
+    {
+        return mime;
+    }
+
+    MIME_TYPES
+        .iter()
+        .find(|(known, _)| *known == extension)
+        .map_or("application/octet-stream", |(_, mime)| mime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_mime_type() {
+        assert_eq!(detect_mime_type("bin", b"%PDF-1.7\n"), "application/pdf");
+        assert_eq!(
+            detect_mime_type("mp4", b"\x00\x00\x00\x18ftyp"),
+            "video/mp4"
+        );
+        assert_eq!(detect_mime_type("", b"hello"), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_get_store_path() {
+        let hash = "ab12cd";
+
+        assert_eq!(
+            get_store_path(Path::new("/misc"), hash, Path::new("Book.PDF")),
+            PathBuf::from("/misc/ab/ab12cd.pdf")
+        );
+    }
+}
@@ -0,0 +1,471 @@
+use crate::database;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+use std::error::Error;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Reference {
+    pub kind: String,
+    pub key: String,
+    pub authors: Option<String>,
+    pub year: Option<String>,
+    pub title: Option<String>,
+    pub publisher: Option<String>,
+    pub doi: Option<String>,
+    pub url: Option<String>,
+}
+
+// Reads the entries of a .bib file, @comment, @string and @preamble blocks are skipped
+pub fn parse(content: &str) -> Result<Vec<Reference>, Box<dyn Error>> {
+    let mut references = Vec::new();
+    let mut chars = content.chars().peekable();
+
+    while skip_to_entry(&mut chars) {
+        let kind = read_while(&mut chars, |c| c.is_alphanumeric()).to_lowercase();
+        skip_whitespace(&mut chars);
+        let close = match chars.next() {
+            Some('{') => '}',
+            Some('(') => ')',
+            _ => return Err(format!("Missing opening brace after '@{}'", kind).into()),
+        };
+
+        if ["comment", "string", "preamble"].contains(&kind.as_str()) {
+            read_braced(&mut chars, close)?;
+            continue;
+        }
+
+        skip_whitespace(&mut chars);
+        let key = read_while(&mut chars, |c| c != ',' && c != close && !c.is_whitespace());
+        if key.is_empty() {
+            return Err(format!("Missing citation key in '@{}' entry", kind).into());
+        }
+        let mut reference = Reference {
+            kind,
+            key,
+            ..Default::default()
+        };
+
+        loop {
+            skip_whitespace(&mut chars);
+            match chars.peek() {
+                Some(',') => {
+                    chars.next();
+                    continue;
+                }
+                Some(c) if *c == close => {
+                    chars.next();
+                    break;
+                }
+                None => {
+                    return Err(format!("Unterminated entry '{}'", reference.key).into());
+                }
+                _ => {}
+            }
+
+            let name = read_while(&mut chars, |c| c != '=' && c != ',' && c != close)
+                .trim()
+                .to_lowercase();
+            if chars.next() != Some('=') {
+                return Err(format!(
+                    "Missing value for field '{}' in entry '{}'",
+                    name, reference.key
+                )
+                .into());
+            }
+            let value = read_value(&mut chars, close)?;
+            set_field(&mut reference, &name, value);
+        }
+
+        references.push(reference);
+    }
+
+    Ok(references)
+}
+
+// Sources are matched by their citation key and then by their name, so importing the same file
+// twice updates the existing sources and sources already used in source: tags get their fields.
+// Fields the entry doesn't have keep their value, e.g. a url set with `cerebra mod source`
+pub fn import(conn: &Connection, references: &[Reference]) -> Result<(), Box<dyn Error>> {
+    let mut added = 0;
+    let mut updated = 0;
+
+    for reference in references {
+        let name = match &reference.title {
+            Some(title) => title.replace(['{', '}'], ""),
+            None => reference.key.clone(),
+        };
+
+        let existing_id: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM source WHERE citation_key = ?1
+                UNION ALL SELECT id FROM source WHERE name = ?2 AND citation_key IS NULL
+                LIMIT 1",
+                params![reference.key, name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let id = match existing_id {
+            Some(id) => {
+                updated += 1;
+                id
+            }
+            None => {
+                conn.execute("INSERT INTO source (name) VALUES (?)", params![name])?;
+                added += 1;
+                conn.last_insert_rowid()
+            }
+        };
+
+        conn.execute(
+            "UPDATE source SET type = ?1, authors = COALESCE(?2, authors),
+                year = COALESCE(?3, year), title = COALESCE(?4, title),
+                publisher = COALESCE(?5, publisher), doi = COALESCE(?6, doi),
+                url = COALESCE(?7, url), citation_key = ?8
+            WHERE id = ?9",
+            params![
+                reference.kind,
+                reference.authors,
+                reference.year,
+                reference.title,
+                reference.publisher,
+                reference.doi,
+                reference.url,
+                reference.key,
+                id
+            ],
+        )?;
+    }
+
+    println!(
+        "Imported {} sources: {} added, {} updated",
+        added + updated,
+        added,
+        updated
+    );
+
+    Ok(())
+}
+
+// Sources without a citation key, e.g. the ones only created by source: tags, are exported as
+// @misc entries with a key derived from their name, or from their id if the name has no letters
+// or digits. A derived key that is taken gets a number, e.g. cosmos-2. Notes without a source
+// point to an unnamed one, which is left out
+pub fn export(conn: &Connection) -> Result<String, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, type, authors, year, title, publisher, doi, url, citation_key, id
+        FROM source
+        WHERE name != ''
+        ORDER BY id",
+    )?;
+    // the citation key if the source has one, the key derived from it otherwise
+    let rows: Vec<(Option<String>, String, Reference)> = stmt
+        .query_map([], |row| {
+            let name: String = row.get(0)?;
+            let derived = match database::init::slugify(&name) {
+                slug if slug.is_empty() => format!("source{}", row.get::<usize, i64>(9)?),
+                slug => slug,
+            };
+            Ok((
+                row.get(8)?,
+                derived,
+                Reference {
+                    kind: row
+                        .get::<usize, Option<String>>(1)?
+                        .unwrap_or("misc".to_string()),
+                    key: String::new(),
+                    authors: row.get(2)?,
+                    year: row.get(3)?,
+                    title: Some(row.get::<usize, Option<String>>(4)?.unwrap_or(name)),
+                    publisher: row.get(5)?,
+                    doi: row.get(6)?,
+                    url: row.get(7)?,
+                },
+            ))
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    let mut keys: HashSet<String> = rows.iter().filter_map(|(key, _, _)| key.clone()).collect();
+    let mut entries = Vec::new();
+    for (key, derived, mut reference) in rows {
+        reference.key = match key {
+            Some(key) => key,
+            None => {
+                let mut key = derived.clone();
+                let mut number = 2;
+                while keys.contains(&key) {
+                    key = format!("{}-{}", derived, number);
+                    number += 1;
+                }
+                keys.insert(key.clone());
+                key
+            }
+        };
+        entries.push(render(&reference));
+    }
+
+    Ok(entries.join("\n"))
+}
+
+pub fn render(reference: &Reference) -> String {
+    // articles are published in a journal
+    let publisher = if reference.kind == "article" {
+        "journal"
+    } else {
+        "publisher"
+    };
+    let fields = [
+        ("author", &reference.authors),
+        ("title", &reference.title),
+        ("year", &reference.year),
+        (publisher, &reference.publisher),
+        ("doi", &reference.doi),
+        ("url", &reference.url),
+    ];
+
+    let mut entry = format!("@{}{{{},\n", reference.kind, reference.key);
+    for (name, value) in fields {
+        if let Some(value) = value {
+            entry.push_str(&format!("  {} = {{{}}},\n", name, value));
+        }
+    }
+    entry.push_str("}\n");
+
+    entry
+}
+
+fn set_field(reference: &mut Reference, name: &str, value: String) {
+    match name {
+        "author" => reference.authors = Some(value),
+        "year" => reference.year = Some(value),
+        "title" => reference.title = Some(value),
+        "publisher" => reference.publisher = Some(value),
+        "journal" if reference.publisher.is_none() => reference.publisher = Some(value),
+        "doi" => reference.doi = Some(value),
+        "url" => reference.url = Some(value),
+        _ => {}
+    }
+}
+
+// An entry starts with @, a type and an opening brace. Any other @, e.g. of an e-mail address in
+// a comment, is free text
+fn skip_to_entry(chars: &mut Peekable<Chars>) -> bool {
+    while let Some(c) = chars.next() {
+        if c != '@' {
+            continue;
+        }
+
+        let mut ahead = chars.clone();
+        let kind = read_while(&mut ahead, |c| c.is_alphanumeric());
+        skip_whitespace(&mut ahead);
+        if kind.starts_with(|c: char| c.is_alphabetic()) && matches!(ahead.peek(), Some('{' | '('))
+        {
+            return true;
+        }
+    }
+
+    false
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn read_while(chars: &mut Peekable<Chars>, condition: impl Fn(char) -> bool) -> String {
+    let mut text = String::new();
+    while let Some(c) = chars.peek() {
+        if !condition(*c) {
+            break;
+        }
+        text.push(*c);
+        chars.next();
+    }
+
+    text
+}
+
+// Reads up to the matching closing character, nested braces are kept as they are
+fn read_braced(chars: &mut Peekable<Chars>, close: char) -> Result<String, Box<dyn Error>> {
+    let mut text = String::new();
+    let mut depth = 0;
+
+    for c in chars.by_ref() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            c if c == close && depth == 0 => return Ok(text),
+            _ => {}
+        }
+        text.push(c);
+    }
+
+    Err("Unbalanced braces".into())
+}
+
+// A value is {braced}, "quoted" or a bare number or macro, parts can be joined with #
+fn read_value(chars: &mut Peekable<Chars>, close: char) -> Result<String, Box<dyn Error>> {
+    let mut value = String::new();
+
+    loop {
+        skip_whitespace(chars);
+        match chars.peek() {
+            Some('{') => {
+                chars.next();
+                value.push_str(&read_braced(chars, '}')?);
+            }
+            Some('"') => {
+                chars.next();
+                value.push_str(&read_braced(chars, '"')?);
+            }
+            _ => value.push_str(
+                read_while(chars, |c| {
+                    c != ',' && c != '#' && c != close && !c.is_whitespace()
+                })
+                .as_str(),
+            ),
+        }
+
+        skip_whitespace(chars);
+        if chars.peek() == Some(&'#') {
+            chars.next();
+        } else {
+            break;
+        }
+    }
+
+    // line breaks inside a value are only formatting
+    Ok(value.split_whitespace().collect::<Vec<&str>>().join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let content = r#"
+@comment{ignored {entry}}
+@Article{knuth1984,
+  author = {Donald E. Knuth},
+  title = "Literate {Programming}",
+  journal = {The Computer
+    Journal},
+  year = 1984,
+  doi = {10.1093/comjnl/27.2.97},
+}
+@book(sicp, title = {Structure and Interpretation} # { of Computer Programs})
+"#;
+        let references = parse(content).unwrap();
+
+        assert_eq!(
+            references,
+            vec![
+                Reference {
+                    kind: "article".to_string(),
+                    key: "knuth1984".to_string(),
+                    authors: Some("Donald E. Knuth".to_string()),
+                    year: Some("1984".to_string()),
+                    title: Some("Literate {Programming}".to_string()),
+                    publisher: Some("The Computer Journal".to_string()),
+                    doi: Some("10.1093/comjnl/27.2.97".to_string()),
+                    url: None,
+                },
+                Reference {
+                    kind: "book".to_string(),
+                    key: "sicp".to_string(),
+                    title: Some("Structure and Interpretation of Computer Programs".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_free_text() {
+        let content = "% contact me@example.com\n@misc{note, title = {a@b}}\nMail @ home\n";
+
+        assert_eq!(
+            parse(content).unwrap(),
+            vec![Reference {
+                kind: "misc".to_string(),
+                key: "note".to_string(),
+                title: Some("a@b".to_string()),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_render_round_trip() {
+        let reference = Reference {
+            kind: "article".to_string(),
+            key: "knuth1984".to_string(),
+            authors: Some("Donald E. Knuth".to_string()),
+            year: Some("1984".to_string()),
+            title: Some("Literate {Programming}".to_string()),
+            publisher: Some("The Computer Journal".to_string()),
+            doi: None,
+            url: Some("https://example.com/lp.pdf".to_string()),
+        };
+
+        assert_eq!(parse(&render(&reference)).unwrap(), vec![reference]);
+    }
+
+    #[test]
+    fn test_export_keys() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE Source (
+                id INTEGER PRIMARY KEY, name TEXT, type TEXT, authors TEXT, year TEXT, title TEXT,
+                publisher TEXT, doi TEXT, url TEXT, citation_key TEXT
+            );
+            INSERT INTO Source (id, name, citation_key) VALUES
+                (1, 'Cosmos', NULL), (2, 'cosmos!', NULL), (3, '???', NULL), (4, '', NULL),
+                (5, 'Pale Blue Dot', 'cosmos-2');",
+        )
+        .unwrap();
+
+        let keys: Vec<String> = parse(&export(&conn).unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|reference| reference.key)
+            .collect();
+        assert_eq!(keys, vec!["cosmos", "cosmos-3", "source3", "cosmos-2"]);
+    }
+
+    #[test]
+    fn test_import_keeps_fields() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE Source (
+                id INTEGER PRIMARY KEY, name TEXT, type TEXT, authors TEXT, year TEXT, title TEXT,
+                publisher TEXT, doi TEXT, url TEXT, citation_key TEXT
+            );
+            INSERT INTO Source (id, name, year, url, citation_key)
+            VALUES (1, 'Cosmos', '1979', 'https://example.com', 'sagan1980');",
+        )
+        .unwrap();
+
+        import(&conn, &parse("@book{sagan1980, year = 1980}").unwrap()).unwrap();
+
+        let fields: (String, String, Option<String>) = conn
+            .query_row(
+                "SELECT year, url, type FROM Source WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            fields,
+            (
+                "1980".to_string(),
+                "https://example.com".to_string(),
+                Some("book".to_string())
+            )
+        );
+    }
+}
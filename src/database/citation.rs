@@ -0,0 +1,196 @@
+use crate::database::bibtex::Reference;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::error::Error;
+
+// Links a note to the sources of the citation keys in its content and returns the keys that
+// don't belong to any source. Links of citations that were removed from the note are dropped,
+// links from attachments stay.
+pub fn link(conn: &Connection, note_id: i64, content: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut unresolved = Vec::new();
+
+    conn.execute(
+        "DELETE FROM NoteHasSource WHERE note_id = ? AND cited = 1",
+        params![note_id],
+    )?;
+
+    for key in parse_citations(content) {
+        match get_source_id(conn, &key)? {
+            Some(source_id) => {
+                conn.execute(
+                    "INSERT INTO NoteHasSource (note_id, source_id, cited)
+                    SELECT ?1, ?2, 1 WHERE NOT EXISTS (
+                        SELECT 1 FROM NoteHasSource WHERE note_id = ?1 AND source_id = ?2
+                    )",
+                    params![note_id, source_id],
+                )?;
+            }
+            None => unresolved.push(key),
+        }
+    }
+
+    Ok(unresolved)
+}
+
+// Appends a bibliography of the sources cited in the content of a topic file, the file itself
+// is left as it is
+pub fn render_bibliography(conn: &Connection, content: &str) -> Result<String, Box<dyn Error>> {
+    let mut entries = Vec::new();
+    for key in parse_citations(content) {
+        if let Some(reference) = get_reference(conn, &key)? {
+            entries.push(format_reference(&reference));
+        }
+    }
+
+    let mut rendered = content.trim_end().to_string();
+    rendered.push('\n');
+    if !entries.is_empty() {
+        rendered.push_str(&format!("\n## Bibliography\n\n{}\n", entries.join("\n")));
+    }
+
+    Ok(rendered)
+}
+
+// Finds the keys of Pandoc citations like [@knuth1984], [see @knuth1984, p. 4] or
+// [@knuth1984; -@wirth1976], every key is only returned once
+pub fn parse_citations(content: &str) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+
+    for (start, _) in content.match_indices('[') {
+        let rest = &content[start + 1..];
+        let end = match rest.find([']', '[']) {
+            Some(end) if rest[end..].starts_with(']') => end,
+            _ => continue,
+        };
+        let citation = &rest[..end];
+
+        let mut previous = ' ';
+        for (i, c) in citation.char_indices() {
+            // an @ inside a word is an email address, not a citation
+            if c == '@' && (previous.is_whitespace() || previous == ';' || previous == '-') {
+                let key: String = citation[i + 1..]
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || "_-:./".contains(*c))
+                    .collect();
+                let key = key.trim_end_matches([':', '.', '/']).to_string();
+                if !key.is_empty() && !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+            previous = c;
+        }
+    }
+
+    keys
+}
+
+fn get_source_id(conn: &Connection, key: &str) -> Result<Option<i64>, Box<dyn Error>> {
+    Ok(conn
+        .query_row(
+            "SELECT id FROM source WHERE citation_key = ?",
+            params![key],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+fn get_reference(conn: &Connection, key: &str) -> Result<Option<Reference>, Box<dyn Error>> {
+    Ok(conn
+        .query_row(
+            "SELECT name, type, authors, year, title, publisher, doi, url
+            FROM source
+            WHERE citation_key = ?",
+            params![key],
+            |row| {
+                let name: String = row.get(0)?;
+                Ok(Reference {
+                    kind: row
+                        .get::<usize, Option<String>>(1)?
+                        .unwrap_or("misc".to_string()),
+                    key: key.to_string(),
+                    authors: row.get(2)?,
+                    year: row.get(3)?,
+                    title: Some(row.get::<usize, Option<String>>(4)?.unwrap_or(name)),
+                    publisher: row.get(5)?,
+                    doi: row.get(6)?,
+                    url: row.get(7)?,
+                })
+            },
+        )
+        .optional()?)
+}
+
+// - **knuth1984** Donald E. Knuth (1984). *Literate Programming*. The Computer Journal.
+fn format_reference(reference: &Reference) -> String {
+    let mut parts = Vec::new();
+    if let Some(authors) = &reference.authors {
+        let authors = authors.replace(" and ", ", ");
+        match &reference.year {
+            Some(year) => parts.push(format!("{} ({})", authors, year)),
+            None => parts.push(authors),
+        }
+    }
+    if let Some(title) = &reference.title {
+        parts.push(format!("*{}*", title.replace(['{', '}'], "")));
+    }
+    if reference.authors.is_none() {
+        if let Some(year) = &reference.year {
+            parts.push(year.to_string());
+        }
+    }
+    if let Some(publisher) = &reference.publisher {
+        parts.push(publisher.to_string());
+    }
+
+    let mut entry = format!("- **{}** {}.", reference.key, parts.join(". "));
+    match (&reference.doi, &reference.url) {
+        (Some(doi), _) => entry.push_str(&format!(" https://doi.org/{}", doi)),
+        (None, Some(url)) => entry.push_str(&format!(" {}", url)),
+        (None, None) => {}
+    }
+
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_citations() {
+        let content = "As shown [@knuth1984], and [see @wirth1976, p. 4; -@knuth1984]. \
+            Mail me at [me@example.com] or [@sicp.] [@broken";
+
+        assert_eq!(
+            parse_citations(content),
+            vec![
+                "knuth1984".to_string(),
+                "wirth1976".to_string(),
+                "sicp".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_bibliography() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE Source (
+                id INTEGER PRIMARY KEY, name TEXT, type TEXT, authors TEXT, year TEXT, title TEXT,
+                publisher TEXT, doi TEXT, url TEXT, citation_key TEXT
+            );
+            INSERT INTO Source (id, name, authors, year, citation_key)
+            VALUES (1, 'Literate Programming', 'Donald E. Knuth', '1984', 'knuth1984');",
+        )
+        .unwrap();
+
+        assert_eq!(
+            render_bibliography(&conn, "# cs\n\nRead it [@knuth1984] [@missing]\n\n").unwrap(),
+            "# cs\n\nRead it [@knuth1984] [@missing]\n\n## Bibliography\n\n\
+            - **knuth1984** Donald E. Knuth (1984). *Literate Programming*.\n"
+        );
+        assert_eq!(
+            render_bibliography(&conn, "# cs\n\nNothing cited\n").unwrap(),
+            "# cs\n\nNothing cited\n"
+        );
+    }
+}
@@ -1,22 +1,837 @@
-use rusqlite::Connection;
+use crate::database;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
 
+// name, file extension and other names the language goes by in file names, shebangs and fences
+const LANGUAGES: [(&str, &str, &[&str]); 20] = [
+    ("rust", "rs", &[]),
+    ("python", "py", &["python3", "python2"]),
+    ("shell", "sh", &["bash", "zsh", "fish", "sh"]),
+    ("javascript", "js", &["node", "mjs", "cjs"]),
+    ("typescript", "ts", &["deno", "tsx"]),
+    ("go", "go", &["golang"]),
+    ("c", "c", &["h"]),
+    ("cpp", "cpp", &["c++", "cc", "cxx", "hpp"]),
+    ("java", "java", &[]),
+    ("kotlin", "kt", &[]),
+    ("ruby", "rb", &[]),
+    ("perl", "pl", &[]),
+    ("lua", "lua", &[]),
+    ("haskell", "hs", &[]),
+    ("sql", "sql", &["sqlite", "psql"]),
+    ("html", "html", &["htm"]),
+    ("css", "css", &[]),
+    ("json", "json", &[]),
+    ("yaml", "yaml", &["yml"]),
+    ("toml", "toml", &[]),
+];
+
+// id, name, snippet path, source path and line range of a snippet taken from a file
+type SourceRow = (i64, String, String, String, Option<usize>, Option<usize>);
+
+#[derive(Debug, PartialEq)]
+pub struct CodeBlock {
+    pub line: usize,
+    pub language: String,
+    pub code: String,
+}
+
+// The snippet is stored in code_path/<language>/<slug>.<extension>
 pub fn add(
-    _conn: &Connection,
-    _content: &str,
-    _tags: HashMap<String, String>,
+    code_directory: &Path,
+    conn: &Connection,
+    name: &str,
+    language: &str,
+    snippet: &str,
+    tags: HashMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    let mut project = String::new();
+    let mut file = String::new();
+    let mut lines = None;
+
+    for (key, value) in &tags {
+        match key.as_str() {
+            "lang" => {}
+            "project" => project = value.to_string(),
+            "file" => file = value.to_string(),
+            "lines" => lines = Some(parse_lines(value)?),
+            _ => {
+                eprintln!(
+                    "Invalid tag '{}'. Use 'lang', 'project', 'file' or 'lines'.",
+                    key
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+    if lines.is_some() && file.is_empty() {
+        return Err("The lines tag needs a file tag as well".into());
+    }
+
+    let (id, _) = add_to_db(code_directory, conn, name, language, snippet)?;
+    if !file.is_empty() {
+        set_source(conn, id, Path::new(&file), lines)?;
+    }
+
+    if !project.is_empty() {
+        let (project_parents, project_child) = database::init::get_parents(&project)?;
+        let project_id = database::project::get_id(conn, project_child, project_parents)?;
+        conn.execute(
+            "UPDATE code SET project_id = ? WHERE id = ?",
+            params![project_id, id],
+        )?;
+    }
+
+    println!(
+        "Added code {}: '{}' with language '{}', project '{}', file '{}'",
+        id,
+        name,
+        language,
+        project,
+        format_source(&file, lines)
+    );
+
+    Ok(())
+}
+
+pub fn add_to_db(
+    code_directory: &Path,
+    conn: &Connection,
+    name: &str,
+    language: &str,
+    snippet: &str,
+) -> Result<(i64, PathBuf), Box<dyn Error>> {
+    let path = get_free_path(code_directory, name, language);
+
+    let language_id = get_language_id(conn, language)?;
+    conn.execute(
+        "INSERT INTO code (name, language_id, path, hash) VALUES (?, ?, ?, ?)",
+        params![name, language_id, path.to_string_lossy(), get_hash(snippet)],
+    )?;
+    let id = conn.last_insert_rowid();
+    conn.execute(
+        "INSERT INTO CodeHasLanguage (code_id, language_id) VALUES (?, ?)",
+        params![id, language_id],
+    )?;
+
+    // the file is written last, so a failed insert doesn't leave it behind, and the entry is
+    // removed again if the file can't be written
+    let written = match path.parent() {
+        Some(parent) => fs::create_dir_all(parent).and_then(|_| fs::write(&path, snippet)),
+        None => fs::write(&path, snippet),
+    };
+    if let Err(err) = written {
+        conn.execute("DELETE FROM CodeHasLanguage WHERE code_id = ?", params![id])?;
+        conn.execute("DELETE FROM code WHERE id = ?", params![id])?;
+        return Err(err.into());
+    }
+
+    Ok((id, path))
+}
+
+// The snippet file is deleted together with the entry
+pub fn remove(conn: &Connection, id: u64) -> Result<(), Box<dyn Error>> {
+    let (name, path) = get_code(conn, id);
+
+    let mut input = String::new();
+    println!("Are you sure you want to delete code '{}'? (y/n)", name);
+    std::io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+
+    if input.trim() != "y" {
+        println!("Aborting deletion");
+        return Ok(());
+    }
+
+    for table in ["CodeHasProject", "CodeHasNote", "CodeHasLanguage"] {
+        conn.execute(
+            &format!("DELETE FROM {} WHERE code_id = ?", table),
+            params![id],
+        )?;
+    }
+    conn.execute("DELETE FROM code WHERE id = ?", params![id])?;
+    if let Some(path) = path.filter(|path| Path::new(path).exists()) {
+        fs::remove_file(path)?;
+    }
+
+    println!("Deleted code {}", id);
+    Ok(())
+}
+
+pub fn modify(
+    conn: &Connection,
+    id: u64,
+    tags: HashMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    get_code(conn, id);
+
+    if tags.is_empty() {
+        eprintln!("No tags provided for modification");
+        std::process::exit(1);
+    }
+
+    for (key, value) in &tags {
+        match key.as_str() {
+            "name" => {
+                conn.execute("UPDATE code SET name = ? WHERE id = ?", params![value, id])?;
+            }
+            "project" => {
+                let project_id = if value.is_empty() {
+                    None
+                } else {
+                    let (project_parents, project_child) = database::init::get_parents(value)?;
+                    Some(database::project::get_id(
+                        conn,
+                        project_child,
+                        project_parents,
+                    )?)
+                };
+                conn.execute(
+                    "UPDATE code SET project_id = ? WHERE id = ?",
+                    params![project_id, id],
+                )?;
+            }
+            "file" => {
+                if value.is_empty() {
+                    conn.execute(
+                        "UPDATE code SET source_path = NULL, line_start = NULL, line_end = NULL
+                        WHERE id = ?",
+                        params![id],
+                    )?;
+                } else {
+                    let lines = match tags.get("lines") {
+                        Some(lines) if !lines.is_empty() => Some(parse_lines(lines)?),
+                        _ => None,
+                    };
+                    set_source(conn, id as i64, Path::new(value), lines)?;
+                }
+            }
+            "lines" => {
+                // handled together with file
+                if !tags.contains_key("file") {
+                    let lines = if value.is_empty() {
+                        None
+                    } else {
+                        Some(parse_lines(value)?)
+                    };
+                    set_lines(conn, id as i64, lines)?;
+                }
+            }
+            _ => {
+                eprintln!(
+                    "Invalid tag '{}'. Use 'name', 'project', 'file' or 'lines'.",
+                    key
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    println!("Modified code {}", id);
+    Ok(())
+}
+
+// Turns the fenced code blocks of all note files into code entries. Blocks are identified by the
+// hash of their content, so running it again only picks up new or changed blocks. The snippet
+// files are added to created, so they can be removed if the transaction is rolled back.
+pub fn extract(
+    note_directory: &Path,
+    code_directory: &Path,
+    conn: &Connection,
+    created: &mut Vec<PathBuf>,
 ) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+    let mut stmt = conn.prepare("SELECT id, content FROM note ORDER BY id")?;
+    let notes: Vec<(i64, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(Result::ok)
+        .collect();
+
+    let mut extracted = 0;
+    let mut linked = 0;
+    let mut skipped = 0;
+    for file in database::init::get_markdown_files(note_directory)? {
+        let content = fs::read_to_string(&file)?;
+        let name = file
+            .strip_prefix(note_directory)
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .to_string();
+
+        for block in parse_blocks(&content) {
+            // a snippet stored before, also one added by hand, is only linked to its note
+            let existing: Option<i64> = conn
+                .query_row(
+                    "SELECT id FROM code WHERE hash = ? ORDER BY id",
+                    params![get_hash(&block.code)],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(id) = existing {
+                match find_note(&content, block.line, &notes) {
+                    Some(note_id) if link_note(conn, id, note_id)? => {
+                        println!("Linked code {} to note {}", id, note_id);
+                        linked += 1;
+                    }
+                    _ => skipped += 1,
+                }
+                continue;
+            }
+
+            let name = format!("{}:{}", name, block.line);
+            let (id, path) = add_to_db(code_directory, conn, &name, &block.language, &block.code)?;
+            created.push(path);
+            extracted += 1;
+
+            match find_note(&content, block.line, &notes) {
+                Some(note_id) => {
+                    link_note(conn, id, note_id)?;
+                    println!(
+                        "Extracted code {}: '{}' with language '{}' from note {}",
+                        id, name, block.language, note_id
+                    );
+                }
+                None => println!(
+                    "Extracted code {}: '{}' with language '{}'",
+                    id, name, block.language
+                ),
+            }
+        }
+    }
+
+    println!(
+        "Extracted {} code blocks, linked {} stored ones to their notes, skipped {} that were already extracted",
+        extracted, linked, skipped
+    );
+    Ok(())
+}
+
+// Returns false if the code was linked to the note already
+fn link_note(conn: &Connection, code_id: i64, note_id: i64) -> Result<bool, Box<dyn Error>> {
+    let added = conn.execute(
+        "INSERT INTO CodeHasNote (code_id, note_id)
+        SELECT ?1, ?2 WHERE NOT EXISTS (
+            SELECT 1 FROM CodeHasNote WHERE code_id = ?1 AND note_id = ?2
+        )",
+        params![code_id, note_id],
+    )?;
+
+    Ok(added > 0)
+}
+
+// Fences are ``` or ~~~ with an optional language, a block without one is stored as text
+pub fn parse_blocks(content: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut lines = content.lines().enumerate();
+
+    while let Some((number, line)) = lines.next() {
+        let line = line.trim_start();
+        let fence: String = line
+            .chars()
+            .take_while(|c| *c == '`' || *c == '~')
+            .collect();
+        if fence.len() < 3 || fence.chars().any(|c| c != fence.chars().next().unwrap()) {
+            continue;
+        }
+
+        let info = line[fence.len()..].split_whitespace().next().unwrap_or("");
+        let language = match info {
+            "" => "text".to_string(),
+            info => parse_language(info)
+                .map(String::from)
+                .unwrap_or(info.to_lowercase()),
+        };
+
+        let mut code = String::new();
+        for (_, line) in lines.by_ref() {
+            if line.trim().starts_with(&fence)
+                && line.trim().trim_start_matches(&fence[..1]).is_empty()
+            {
+                break;
+            }
+            code.push_str(line);
+            code.push('\n');
+        }
+
+        if !code.trim().is_empty() {
+            blocks.push(CodeBlock {
+                line: number + 1,
+                language,
+                code,
+            });
+        }
+    }
+
+    blocks
+}
+
+// The note a block belongs to is the anchored note around it. In files written before anchors were
+// added, it is the last note above the block found by its first line, as long as no other note
+// starts with the same line.
+fn find_note(content: &str, line: usize, notes: &[(i64, String)]) -> Option<i64> {
+    let above = content.lines().take(line - 1);
+
+    if content
+        .lines()
+        .any(|text| database::note::parse_anchor(text).is_some())
+    {
+        let mut open = None;
+        for text in above {
+            if let Some(id) = database::note::parse_anchor(text) {
+                open = Some(id);
+            } else if open.is_some_and(|id| text.trim() == format!("<!-- /cerebra:{} -->", id)) {
+                open = None;
+            }
+        }
+        return open.filter(|id| notes.iter().any(|(note, _)| note == id));
+    }
+
+    let mut note_id = None;
+    for text in above {
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let matches: Vec<i64> = notes
+            .iter()
+            .filter(|(_, note)| {
+                note.lines()
+                    .next()
+                    .is_some_and(|first| first.trim() == text)
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        match matches.as_slice() {
+            [] => {}
+            [id] => note_id = Some(*id),
+            _ => note_id = None,
+        }
+    }
+
+    note_id
+}
+
+// Compares every snippet that was taken from a file with that file. Snippets that only moved get
+// their new line range, for changed ones the user is asked whether to take over the new version.
+pub fn check(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, path, source_path, line_start, line_end FROM code
+        WHERE source_path IS NOT NULL AND path IS NOT NULL
+        ORDER BY id",
+    )?;
+    let sources: Vec<SourceRow> = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+            ))
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    let (mut current, mut moved, mut drifted, mut missing) = (0, 0, 0, 0);
+    for (id, name, path, source_path, line_start, line_end) in sources {
+        let lines = line_start.zip(line_end);
+        let snippet = match fs::read_to_string(&path) {
+            Ok(snippet) => snippet,
+            Err(err) => {
+                println!("Code {} '{}': failed to read {}: {}", id, name, path, err);
+                missing += 1;
+                continue;
+            }
+        };
+        let file = match fs::read_to_string(&source_path) {
+            Ok(file) => file,
+            Err(_) => {
+                println!("Code {} '{}': {} no longer exists", id, name, source_path);
+                drifted += 1;
+                continue;
+            }
+        };
+
+        let excerpt = get_lines(&file, lines);
+        if excerpt.as_deref() == Some(snippet.as_str()) {
+            current += 1;
+            continue;
+        }
+
+        if let Some(start) = lines.and_then(|_| find_lines(&file, &snippet)) {
+            let lines = (start, start + snippet.lines().count() - 1);
+            set_lines(conn, id, Some(lines))?;
+            println!(
+                "Code {} '{}' moved to {}",
+                id,
+                name,
+                format_source(&source_path, Some(lines))
+            );
+            moved += 1;
+            continue;
+        }
+
+        drifted += 1;
+        println!(
+            "Code {} '{}' no longer matches {}",
+            id,
+            name,
+            format_source(&source_path, lines)
+        );
+        let excerpt = match excerpt {
+            Some(excerpt) => excerpt,
+            None => {
+                println!("  the file is shorter than the line range, nothing to refresh from");
+                continue;
+            }
+        };
+
+        let mut input = String::new();
+        println!("Refresh the snippet from the file? (y/n)");
+        std::io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read line");
+        if input.trim() == "y" {
+            fs::write(&path, &excerpt)?;
+            conn.execute(
+                "UPDATE code SET hash = ? WHERE id = ?",
+                params![get_hash(&excerpt), id],
+            )?;
+            println!("Refreshed code {}", id);
+        }
+    }
+
+    println!(
+        "Checked {} snippets: {} up to date, {} moved, {} changed, {} unreadable",
+        current + moved + drifted + missing,
+        current,
+        moved,
+        drifted,
+        missing
+    );
     Ok(())
 }
 
-pub fn remove(_conn: &Connection, _id: u64) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+// Reads a snippet from a file, e.g. for file:src/main.rs lines:10-25
+pub fn read_source(path: &Path, lines: Option<(usize, usize)>) -> Result<String, Box<dyn Error>> {
+    let file = fs::read_to_string(path)?;
+
+    get_lines(&file, lines).ok_or(
+        format!(
+            "{} has fewer lines than {}",
+            path.display(),
+            format_source("", lines)
+        )
+        .into(),
+    )
+}
+
+// Line ranges are inclusive and start at 1, without a range the whole file is used
+fn get_lines(content: &str, lines: Option<(usize, usize)>) -> Option<String> {
+    let (start, end) = match lines {
+        Some(lines) => lines,
+        None => return Some(content.to_string()),
+    };
+
+    let excerpt: Vec<&str> = content
+        .lines()
+        .skip(start - 1)
+        .take(end - start + 1)
+        .collect();
+    if excerpt.len() != end - start + 1 {
+        return None;
+    }
+
+    Some(format!("{}\n", excerpt.join("\n")))
+}
+
+// First line number at which the snippet appears unchanged in the file
+fn find_lines(content: &str, snippet: &str) -> Option<usize> {
+    let lines: Vec<&str> = content.lines().collect();
+    let snippet: Vec<&str> = snippet.lines().collect();
+    if snippet.is_empty() {
+        return None;
+    }
+
+    lines
+        .windows(snippet.len())
+        .position(|window| window == snippet.as_slice())
+        .map(|position| position + 1)
+}
+
+pub fn parse_lines(value: &str) -> Result<(usize, usize), Box<dyn Error>> {
+    let invalid = || format!("Invalid line range '{}'. Use e.g. 10-25.", value);
+
+    let (start, end) = match value.split_once('-') {
+        Some((start, end)) => (start, end),
+        None => (value, value),
+    };
+    let start: usize = start.trim().parse().map_err(|_| invalid())?;
+    let end: usize = end.trim().parse().map_err(|_| invalid())?;
+    if start == 0 || end < start {
+        return Err(invalid().into());
+    }
+
+    Ok((start, end))
+}
+
+fn format_source(path: &str, lines: Option<(usize, usize)>) -> String {
+    match lines {
+        Some((start, end)) => format!("{}:{}-{}", path, start, end),
+        None => path.to_string(),
+    }
+}
+
+fn set_source(
+    conn: &Connection,
+    id: i64,
+    path: &Path,
+    lines: Option<(usize, usize)>,
+) -> Result<(), Box<dyn Error>> {
+    // relative paths would break as soon as cerebra runs from another directory
+    let path = fs::canonicalize(path)
+        .map_err(|err| format!("Failed to find {}: {}", path.display(), err))?;
+    conn.execute(
+        "UPDATE code SET source_path = ? WHERE id = ?",
+        params![path.to_string_lossy(), id],
+    )?;
+
+    set_lines(conn, id, lines)
+}
+
+fn set_lines(
+    conn: &Connection,
+    id: i64,
+    lines: Option<(usize, usize)>,
+) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "UPDATE code SET line_start = ?, line_end = ? WHERE id = ?",
+        params![
+            lines.map(|(start, _)| start as i64),
+            lines.map(|(_, end)| end as i64),
+            id
+        ],
+    )?;
+
     Ok(())
 }
 
-pub fn modify(_conn: &Connection, _tags: HashMap<String, String>) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+pub fn get_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+pub fn get_path(conn: &Connection, id: u64) -> Result<PathBuf, Box<dyn Error>> {
+    let (_, path) = get_code(conn, id);
+
+    Ok(PathBuf::from(
+        path.ok_or(format!("Code {} has no file", id))?,
+    ))
+}
+
+// Records the hash of a snippet file that was changed outside of cerebra, e.g. in the editor
+pub fn update_hash(conn: &Connection, id: u64) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "UPDATE code SET hash = ? WHERE id = ?",
+        params![get_hash(&get_snippet(conn, id)?), id],
+    )?;
+
     Ok(())
 }
+
+pub fn get_snippet(conn: &Connection, id: u64) -> Result<String, Box<dyn Error>> {
+    let (_, path) = get_code(conn, id);
+    let path = path.ok_or(format!("Code {} has no file", id))?;
+
+    Ok(fs::read_to_string(path)?)
+}
+
+// Accepts the name, the extension or another common name of a language, e.g. rust, rs or py
+pub fn parse_language(value: &str) -> Option<&'static str> {
+    let value = value.trim().to_lowercase();
+
+    LANGUAGES
+        .iter()
+        .find(|(name, extension, aliases)| {
+            *name == value || *extension == value || aliases.contains(&value.as_str())
+        })
+        .map(|(name, _, _)| *name)
+}
+
+// Looks at the extension of the name first, then at the shebang of the snippet
+pub fn detect_language(name: &str, snippet: &str) -> Option<&'static str> {
+    if let Some(language) = Path::new(name)
+        .extension()
+        .and_then(|extension| parse_language(&extension.to_string_lossy()))
+    {
+        return Some(language);
+    }
+
+    // #!/usr/bin/env python3 or #!/bin/bash
+    let shebang = snippet.lines().next()?.strip_prefix("#!")?;
+    let mut words = shebang.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-'))?;
+    }
+
+    parse_language(interpreter)
+}
+
+pub fn get_extension(language: &str) -> &str {
+    LANGUAGES
+        .iter()
+        .find(|(name, _, _)| *name == language)
+        .map_or("txt", |(_, extension, _)| extension)
+}
+
+fn get_language_id(conn: &Connection, language: &str) -> Result<i64, Box<dyn Error>> {
+    let id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM CodeLanguage WHERE name = ?",
+            params![language],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match id {
+        Some(id) => Ok(id),
+        None => {
+            conn.execute(
+                "INSERT INTO CodeLanguage (name) VALUES (?)",
+                params![language],
+            )?;
+            Ok(conn.last_insert_rowid())
+        }
+    }
+}
+
+// main.rs is stored as main.rs rather than main-rs.rs, existing files are never overwritten
+fn get_free_path(code_directory: &Path, name: &str, language: &str) -> PathBuf {
+    let extension = get_extension(language);
+    let stem = match name.strip_suffix(&format!(".{}", extension)) {
+        Some(stem) => stem,
+        None => name,
+    };
+    let slug = database::init::slugify(stem);
+    let directory = code_directory.join(language);
+
+    let mut path = directory.join(format!("{}.{}", slug, extension));
+    let mut number = 2;
+    while path.exists() {
+        path = directory.join(format!("{}-{}.{}", slug, number, extension));
+        number += 1;
+    }
+
+    path
+}
+
+fn get_code(conn: &Connection, id: u64) -> (String, Option<String>) {
+    let code: Option<(String, Option<String>)> = conn
+        .query_row(
+            "SELECT name, path FROM code WHERE id = ?",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .unwrap_or(None);
+
+    match code {
+        Some(code) => code,
+        None => {
+            eprintln!("Code {} does not exist", id);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(detect_language("main.rs", ""), Some("rust"));
+        assert_eq!(
+            detect_language("backup", "#!/usr/bin/env -S python3 -u\nprint()"),
+            Some("python")
+        );
+        assert_eq!(detect_language("deploy", "#!/bin/bash\n"), Some("shell"));
+        assert_eq!(detect_language("notes", "plain text"), None);
+    }
+
+    #[test]
+    fn test_parse_blocks() {
+        let content = "# rust\n\n## ownership\n\nMoving a value\n```rust\nlet b = a;\n```\n\n\
+            ~~~\nplain\n~~~\n````py title\n```\nprint()\n````\n```\n\n```\n";
+
+        assert_eq!(
+            parse_blocks(content),
+            vec![
+                CodeBlock {
+                    line: 6,
+                    language: "rust".to_string(),
+                    code: "let b = a;\n".to_string(),
+                },
+                CodeBlock {
+                    line: 10,
+                    language: "text".to_string(),
+                    code: "plain\n".to_string(),
+                },
+                CodeBlock {
+                    line: 13,
+                    language: "python".to_string(),
+                    code: "```\nprint()\n".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            find_note(content, 6, &[(1, "Moving a value".to_string())]),
+            Some(1)
+        );
+        // the same first line in two notes doesn't tell them apart
+        assert_eq!(
+            find_note(
+                content,
+                6,
+                &[
+                    (1, "Moving a value".to_string()),
+                    (2, "Moving a value\nagain".to_string())
+                ]
+            ),
+            None
+        );
+
+        let anchored = "<!-- cerebra:7 -->\nText\n```\ncode\n```\n<!-- /cerebra:7 -->\n\
+            Text\n```\nmore\n```\n";
+        let notes = [(7, "Text".to_string()), (8, "Text".to_string())];
+        assert_eq!(find_note(anchored, 3, &notes), Some(7));
+        assert_eq!(find_note(anchored, 8, &notes), None);
+    }
+
+    #[test]
+    fn test_source_lines() {
+        let file = "fn main() {\n    run();\n}\n\nfn run() {}\n";
+
+        assert_eq!(parse_lines("2-3").unwrap(), (2, 3));
+        assert!(parse_lines("3-2").is_err());
+        assert_eq!(
+            get_lines(file, Some((2, 3))),
+            Some("    run();\n}\n".to_string())
+        );
+        assert_eq!(get_lines(file, Some((5, 6))), None);
+        assert_eq!(find_lines(file, "fn run() {}\n"), Some(5));
+        assert_eq!(find_lines(file, "fn walk() {}\n"), None);
+    }
+
+    #[test]
+    fn test_parse_language() {
+        assert_eq!(parse_language("RS"), Some("rust"));
+        assert_eq!(parse_language("yml"), Some("yaml"));
+        assert_eq!(parse_language("brainfuck"), None);
+    }
+}
@@ -0,0 +1,191 @@
+use petgraph::algo::is_cyclic_directed;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::Direction;
+use rusqlite::{params, Connection};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+
+// Edges point from a task to the task it depends on
+pub struct DependencyGraph {
+    pub graph: DiGraph<i64, ()>,
+    pub nodes: HashMap<i64, NodeIndex>,
+}
+
+impl DependencyGraph {
+    pub fn new(edges: &[(i64, i64)]) -> DependencyGraph {
+        let mut graph = DependencyGraph {
+            graph: DiGraph::new(),
+            nodes: HashMap::new(),
+        };
+
+        for (task_id, depends_on_id) in edges {
+            let from = graph.node(*task_id);
+            let to = graph.node(*depends_on_id);
+            graph.graph.add_edge(from, to, ());
+        }
+
+        graph
+    }
+
+    fn node(&mut self, task_id: i64) -> NodeIndex {
+        match self.nodes.get(&task_id) {
+            Some(node) => *node,
+            None => {
+                let node = self.graph.add_node(task_id);
+                self.nodes.insert(task_id, node);
+                node
+            }
+        }
+    }
+
+    // Upstream tasks are the ones a task depends on, downstream tasks depend on it
+    pub fn neighbors(&self, task_id: i64, direction: Direction) -> Vec<i64> {
+        let mut neighbors: Vec<i64> = match self.nodes.get(&task_id) {
+            Some(node) => self
+                .graph
+                .neighbors_directed(*node, direction)
+                .map(|neighbor| self.graph[neighbor])
+                .collect(),
+            None => Vec::new(),
+        };
+        neighbors.sort();
+
+        neighbors
+    }
+}
+
+pub fn get_edges(conn: &Connection) -> Result<Vec<(i64, i64)>, Box<dyn Error>> {
+    let mut stmt = conn.prepare("SELECT task_id, depends_on_id FROM TaskDependsOnTask")?;
+    let edges: Vec<(i64, i64)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(edges)
+}
+
+// Ids given twice are only kept once
+pub fn parse_depends(value: &str) -> Result<Vec<i64>, Box<dyn Error>> {
+    let mut depends_on = Vec::new();
+    for id in value.split(',').filter(|id| !id.trim().is_empty()) {
+        let id = id
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| format!("Invalid task id '{}' in depends", id))?;
+        if !depends_on.contains(&id) {
+            depends_on.push(id);
+        }
+    }
+
+    Ok(depends_on)
+}
+
+pub fn check_exists(conn: &Connection, depends_on: &[i64]) -> Result<(), Box<dyn Error>> {
+    for depends_on_id in depends_on {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM task WHERE id = ?)",
+            params![depends_on_id],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Err(format!("Task {} does not exist", depends_on_id).into());
+        }
+    }
+
+    Ok(())
+}
+
+// Replaces the dependencies of a task, refusing anything that would close a cycle
+pub fn set(conn: &Connection, task_id: i64, depends_on: &[i64]) -> Result<(), Box<dyn Error>> {
+    check_exists(conn, depends_on)?;
+
+    let edges: Vec<(i64, i64)> = get_edges(conn)?
+        .into_iter()
+        .filter(|(from, _)| *from != task_id)
+        .collect();
+    if creates_cycle(&edges, task_id, depends_on) {
+        return Err(format!(
+            "Task {} cannot depend on {:?}, that would create a dependency cycle",
+            task_id, depends_on
+        )
+        .into());
+    }
+
+    conn.execute(
+        "DELETE FROM TaskDependsOnTask WHERE task_id = ?",
+        params![task_id],
+    )?;
+    for depends_on_id in depends_on {
+        conn.execute(
+            "INSERT INTO TaskDependsOnTask (task_id, depends_on_id) VALUES (?, ?)",
+            params![task_id, depends_on_id],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn creates_cycle(edges: &[(i64, i64)], task_id: i64, depends_on: &[i64]) -> bool {
+    let mut edges = edges.to_vec();
+    edges.extend(
+        depends_on
+            .iter()
+            .map(|depends_on_id| (task_id, *depends_on_id)),
+    );
+
+    is_cyclic_directed(&DependencyGraph::new(&edges).graph)
+}
+
+// A task is blocked while one of its dependencies is pending, and blocking while it is pending
+// itself and another pending task depends on it
+pub fn get_blocked_and_blocking(
+    conn: &Connection,
+) -> Result<(HashSet<i64>, HashSet<i64>), Box<dyn Error>> {
+    let query = "SELECT dependency.task_id, dependency.depends_on_id
+        FROM TaskDependsOnTask AS dependency
+        JOIN task AS dependent ON dependent.id = dependency.task_id
+        JOIN task AS upstream ON upstream.id = dependency.depends_on_id
+        WHERE dependent.status = 'pending' AND upstream.status = 'pending'";
+    let mut stmt = conn.prepare(query)?;
+    let edges: Vec<(i64, i64)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(Result::ok)
+        .collect();
+
+    let blocked = edges.iter().map(|(task_id, _)| *task_id).collect();
+    let blocking = edges
+        .iter()
+        .map(|(_, depends_on_id)| *depends_on_id)
+        .collect();
+
+    Ok((blocked, blocking))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_creates_cycle() {
+        let edges = [(2, 1), (3, 2)];
+
+        assert!(!creates_cycle(&edges, 4, &[3, 1]));
+        assert!(creates_cycle(&edges, 1, &[3]));
+        assert!(creates_cycle(&edges, 1, &[1]));
+    }
+
+    #[test]
+    fn test_parse_depends() {
+        assert_eq!(parse_depends("3, 1,3,").unwrap(), vec![3, 1]);
+        assert!(parse_depends("3,x").is_err());
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let graph = DependencyGraph::new(&[(2, 1), (3, 1), (3, 2)]);
+
+        assert_eq!(graph.neighbors(3, Direction::Outgoing), vec![1, 2]);
+        assert_eq!(graph.neighbors(1, Direction::Incoming), vec![2, 3]);
+        assert!(graph.neighbors(4, Direction::Outgoing).is_empty());
+    }
+}
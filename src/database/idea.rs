@@ -1,22 +1,275 @@
-use rusqlite::Connection;
+use crate::database;
+use rusqlite::{params, Connection, OptionalExtension};
 use std::collections::HashMap;
 use std::error::Error;
 
+// seed -> exploring -> validated -> promoted, an idea can be discarded at any point before that
+const STAGES: [&str; 5] = ["seed", "exploring", "validated", "promoted", "discarded"];
+
 pub fn add(
-    _conn: &Connection,
-    _content: &str,
-    _tags: HashMap<String, String>,
+    conn: &Connection,
+    content: &str,
+    tags: HashMap<String, String>,
 ) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+    let mut stage = "seed".to_string();
+    let mut topics = Vec::new();
+
+    for (key, value) in &tags {
+        match key.as_str() {
+            "stage" => {
+                stage = parse_stage(value)?;
+                if stage == "promoted" {
+                    return Err("Ideas are promoted with `cerebra idea promote <id>`".into());
+                }
+            }
+            "topic" => topics = split_topics(value),
+            _ => {
+                eprintln!("Invalid tag '{}'. Use 'stage' or 'topic'.", key);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO idea (name, stage) VALUES (?, ?)",
+        params![content, stage],
+    )?;
+    let id = conn.last_insert_rowid();
+    set_topics(conn, id, &topics)?;
+
+    println!(
+        "Added idea {}: '{}' with stage '{}', topic '{}'",
+        id,
+        content,
+        stage,
+        topics.join(",")
+    );
+
     Ok(())
 }
 
-pub fn remove(_conn: &Connection, _id: u64) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+pub fn remove(conn: &Connection, id: u64) -> Result<(), Box<dyn Error>> {
+    let (name, _) = get_idea(conn, id);
+
+    let mut input = String::new();
+    println!("Are you sure you want to delete idea '{}'? (y/n)", name);
+    std::io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+
+    if input.trim() != "y" {
+        println!("Aborting deletion");
+        return Ok(());
+    }
+
+    conn.execute("DELETE FROM IdeaHasTopic WHERE idea_id = ?", params![id])?;
+    conn.execute("DELETE FROM IdeaHasTask WHERE idea_id = ?", params![id])?;
+    conn.execute("DELETE FROM ProjectHasIdea WHERE idea_id = ?", params![id])?;
+    conn.execute(
+        "UPDATE project SET idea_id = NULL WHERE idea_id = ?",
+        params![id],
+    )?;
+    conn.execute("DELETE FROM idea WHERE id = ?", params![id])?;
+
+    println!("Deleted idea {}", id);
     Ok(())
 }
 
-pub fn modify(_conn: &Connection, _tags: HashMap<String, String>) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+pub fn modify(
+    conn: &Connection,
+    id: u64,
+    tags: HashMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    let (_, current_stage) = get_idea(conn, id);
+
+    if tags.is_empty() {
+        eprintln!("No tags provided for modification");
+        std::process::exit(1);
+    }
+
+    for (key, value) in &tags {
+        match key.as_str() {
+            "content" => {
+                conn.execute("UPDATE idea SET name = ? WHERE id = ?", params![value, id])?;
+            }
+            "stage" => {
+                let stage = parse_stage(value)?;
+                // promoting has to create the project as well
+                if stage == "promoted" {
+                    return Err(
+                        format!("Ideas are promoted with `cerebra idea promote {}`", id).into(),
+                    );
+                }
+                if current_stage == "promoted" {
+                    return Err(format!("Idea {} has already been promoted", id).into());
+                }
+                conn.execute("UPDATE idea SET stage = ? WHERE id = ?", params![stage, id])?;
+            }
+            "topic" => {
+                conn.execute("DELETE FROM IdeaHasTopic WHERE idea_id = ?", params![id])?;
+                set_topics(conn, id as i64, &split_topics(value))?;
+            }
+            _ => {
+                eprintln!("Invalid tag '{}'. Use 'content', 'stage' or 'topic'.", key);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    println!("Modified idea {}", id);
     Ok(())
 }
+
+// Turns an idea into a project that carries over its topics, with the idea text as its first note
+// Returns the id, content and topic of the note made from the idea, its file is left to the caller
+pub fn promote(
+    conn: &Connection,
+    id: u64,
+    name: Option<&str>,
+) -> Result<(i64, String, String), Box<dyn Error>> {
+    let (content, stage) = get_idea(conn, id);
+    if stage == "promoted" || stage == "discarded" {
+        return Err(format!("Idea {} is {} and cannot be promoted", id, stage).into());
+    }
+
+    // a slash in the idea would nest the project, a name given on purpose may do that
+    let path = match name {
+        Some(name) => name.to_string(),
+        None => database::init::slugify(&content),
+    };
+    if path.is_empty() {
+        return Err(format!("Idea {} needs a project name to be promoted", id).into());
+    }
+    // promoting into an existing project would take over its idea and links
+    if let Some(project_id) = database::project::find_id(conn, &path)? {
+        eprintln!(
+            "Project '{}' already exists with id {}. Use --name to promote the idea into a new project.",
+            path, project_id
+        );
+        std::process::exit(1);
+    }
+    let (parents, project_name) = database::init::get_parents(&path)?;
+    let project_id = database::project::get_id(conn, project_name, parents)?;
+    let path = database::project::get_path(conn, project_id)?;
+
+    conn.execute(
+        "UPDATE project SET idea_id = ? WHERE id = ?",
+        params![id, project_id],
+    )?;
+    conn.execute(
+        "INSERT INTO ProjectHasIdea (project_id, idea_id)
+        SELECT ?1, ?2 WHERE NOT EXISTS (
+            SELECT 1 FROM ProjectHasIdea WHERE project_id = ?1 AND idea_id = ?2
+        )",
+        params![project_id, id],
+    )?;
+    conn.execute(
+        "UPDATE idea SET stage = 'promoted', project_id = ? WHERE id = ?",
+        params![project_id, id],
+    )?;
+
+    let topics = get_topics(conn, id as i64)?;
+    for topic_id in &topics {
+        conn.execute(
+            "INSERT INTO ProjectHasTopic (project_id, topic_id)
+            SELECT ?1, ?2 WHERE NOT EXISTS (
+                SELECT 1 FROM ProjectHasTopic WHERE project_id = ?1 AND topic_id = ?2
+            )",
+            params![project_id, topic_id],
+        )?;
+    }
+
+    println!("Promoted idea {} to project {}: '{}'", id, project_id, path);
+
+    // the note is filed under the first topic of the idea, or under ideas if it has none
+    let topic = match topics.first() {
+        Some(topic_id) => database::topic::get_path(conn, *topic_id)?,
+        None => "ideas".to_string(),
+    };
+    let mut tags = HashMap::new();
+    tags.insert("topic".to_string(), topic);
+    tags.insert("project".to_string(), path);
+    let (note_id, topic) = database::note::add_entry(conn, &content, tags)?;
+
+    Ok((note_id, content, topic))
+}
+
+fn get_idea(conn: &Connection, id: u64) -> (String, String) {
+    let idea: Option<(String, String)> = conn
+        .query_row(
+            "SELECT name, stage FROM idea WHERE id = ?",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .unwrap_or(None);
+
+    match idea {
+        Some(idea) => idea,
+        None => {
+            eprintln!("Idea {} does not exist", id);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn get_topics(conn: &Connection, id: i64) -> Result<Vec<i64>, Box<dyn Error>> {
+    let mut stmt =
+        conn.prepare("SELECT topic_id FROM IdeaHasTopic WHERE idea_id = ? ORDER BY rowid")?;
+    let topics: Vec<i64> = stmt
+        .query_map(params![id], |row| row.get(0))?
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(topics)
+}
+
+fn set_topics(conn: &Connection, id: i64, topics: &[String]) -> Result<(), Box<dyn Error>> {
+    for topic in topics {
+        let (topic_parents, topic_child) = database::init::get_parents(topic)?;
+        let topic_id = database::topic::get_id(conn, topic_child, topic_parents)?;
+        conn.execute(
+            "INSERT INTO IdeaHasTopic (idea_id, topic_id) VALUES (?, ?)",
+            params![id, topic_id],
+        )?;
+    }
+
+    Ok(())
+}
+
+// topic:science/physics,writing links an idea to several topics
+fn split_topics(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|topic| !topic.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn parse_stage(value: &str) -> Result<String, Box<dyn Error>> {
+    let stage = value.to_lowercase();
+    if !STAGES.contains(&stage.as_str()) {
+        return Err(format!(
+            "Invalid stage '{}'. Use 'seed', 'exploring', 'validated', 'promoted' or 'discarded'.",
+            value
+        )
+        .into());
+    }
+
+    Ok(stage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_topics() {
+        assert_eq!(
+            split_topics("science/physics, hobby,"),
+            vec!["science/physics".to_string(), "hobby".to_string()]
+        );
+        assert!(split_topics("").is_empty());
+    }
+}
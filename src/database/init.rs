@@ -1,9 +1,62 @@
+use chrono::{Local, NaiveDate};
 use rusqlite::{params, Connection, Result};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
+// Migrations upgrade databases created by older versions, each one runs once and the number of
+// migrations that already ran is stored in user_version. New migrations are only appended
+type Migration = fn(&Connection) -> Result<(), Box<dyn std::error::Error>>;
+const MIGRATIONS: &[Migration] = &[
+    migrate_task_status,
+    migrate_task_recurrence,
+    migrate_project_status,
+    migrate_task_position,
+    migrate_task_uuid,
+    migrate_task_dates,
+    migrate_idea_stage,
+    migrate_writing_files,
+    migrate_writing_goals,
+    migrate_writing_note_position,
+    migrate_code_files,
+    migrate_code_hash,
+    migrate_code_sources,
+    migrate_source_references,
+    migrate_note_citations,
+];
+
 pub fn create_db_tables(db_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let conn = Connection::open(db_path)?;
+    if let Err(e) = create_tables(&conn) {
+        fs::remove_file(db_path)?;
+        return Err(e);
+    }
+
+    // a new database already has the latest schema
+    conn.pragma_update(None, "user_version", MIGRATIONS.len() as i64)?;
+
+    Ok(())
+}
+
+pub fn migrate(conn: &mut Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version as usize >= MIGRATIONS.len() {
+        return Ok(());
+    }
+
+    let transaction = conn.transaction()?;
+    // tables that didn't exist yet are created with all their columns
+    create_tables(&transaction)?;
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(&transaction)?;
+    }
+    transaction.pragma_update(None, "user_version", MIGRATIONS.len() as i64)?;
+    transaction.commit()?;
+
+    Ok(())
+}
+
+fn create_tables(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
     let statements = [
         "CREATE TABLE IF NOT EXISTS Note (
             id INTEGER PRIMARY KEY,
@@ -30,25 +83,62 @@ pub fn create_db_tables(db_path: &Path) -> Result<(), Box<dyn std::error::Error>
         )",
         "CREATE TABLE IF NOT EXISTS Source (
             id INTEGER PRIMARY KEY,
-            name TEXT NOT NULL
+            name TEXT NOT NULL,
+            type CHAR(20),
+            authors TEXT,
+            year CHAR(10),
+            title TEXT,
+            publisher TEXT,
+            doi TEXT,
+            url TEXT,
+            citation_key TEXT UNIQUE
+        )",
+        "CREATE TABLE IF NOT EXISTS Attachment (
+            id INTEGER PRIMARY KEY,
+            source_id INTEGER NOT NULL,
+            path TEXT NOT NULL,
+            original_name TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            mime CHAR(100) NOT NULL,
+            hash TEXT NOT NULL,
+            added TEXT NOT NULL,
+            FOREIGN KEY (source_id) REFERENCES Source(id)
         )",
         "CREATE TABLE IF NOT EXISTS Project (
             id INTEGER PRIMARY KEY,
             name CHAR(50) NOT NULL,
+            status CHAR(10) NOT NULL DEFAULT 'active',
+            start TEXT,
+            target TEXT,
+            parent_project_id INTEGER,
             topic_id INTEGER,
             idea_id INTEGER,
+            FOREIGN KEY (parent_project_id) REFERENCES Project(id),
             FOREIGN KEY (topic_id) REFERENCES Topic(id),
             FOREIGN KEY (idea_id) REFERENCES Idea(id)
         )",
         "CREATE TABLE IF NOT EXISTS Task (
             id INTEGER PRIMARY KEY,
-            name CHAR(50) NOT NULL,
-            project_id INTEGER,
-            FOREIGN KEY (project_id) REFERENCES Project(id)
+            name TEXT NOT NULL,
+            status CHAR(10) NOT NULL DEFAULT 'pending',
+            priority CHAR(1),
+            due TEXT,
+            scheduled TEXT,
+            wait TEXT,
+            until TEXT,
+            entry TEXT NOT NULL,
+            modified TEXT NOT NULL,
+            end TEXT,
+            recur TEXT,
+            parent_id INTEGER,
+            position INTEGER,
+            uuid TEXT UNIQUE,
+            FOREIGN KEY (parent_id) REFERENCES Task(id)
         )",
         "CREATE TABLE IF NOT EXISTS Idea (
             id INTEGER PRIMARY KEY,
             name CHAR(50) NOT NULL,
+            stage CHAR(10) NOT NULL DEFAULT 'seed',
             topic_id INTEGER,
             project_id INTEGER,
             FOREIGN KEY (topic_id) REFERENCES Topic(id),
@@ -57,6 +147,13 @@ pub fn create_db_tables(db_path: &Path) -> Result<(), Box<dyn std::error::Error>
         "CREATE TABLE IF NOT EXISTS Writing (
             id INTEGER PRIMARY KEY,
             name CHAR(50) NOT NULL,
+            kind CHAR(10) NOT NULL DEFAULT 'writing',
+            path TEXT UNIQUE,
+            date TEXT,
+            words INTEGER NOT NULL DEFAULT 0,
+            modified TEXT,
+            goal INTEGER,
+            daily_goal INTEGER,
             project_id INTEGER,
             note_id INTEGER,
             FOREIGN KEY (project_id) REFERENCES Project(id),
@@ -66,6 +163,11 @@ pub fn create_db_tables(db_path: &Path) -> Result<(), Box<dyn std::error::Error>
             id INTEGER PRIMARY KEY,
             name CHAR(50) NOT NULL,
             language_id INTEGER NOT NULL,
+            path TEXT,
+            hash TEXT,
+            source_path TEXT,
+            line_start INTEGER,
+            line_end INTEGER,
             project_id INTEGER,
             note_id INTEGER,
             FOREIGN KEY (project_id) REFERENCES Project(id),
@@ -74,7 +176,7 @@ pub fn create_db_tables(db_path: &Path) -> Result<(), Box<dyn std::error::Error>
         )",
         "CREATE TABLE IF NOT EXISTS CodeLanguage (
             id INTEGER PRIMARY KEY,
-            name CHAR(50) NOT NULL
+            name CHAR(50) NOT NULL UNIQUE
         )",
         "CREATE TABLE IF NOT EXISTS NoteHasTopic (
             note_id INTEGER,
@@ -91,6 +193,7 @@ pub fn create_db_tables(db_path: &Path) -> Result<(), Box<dyn std::error::Error>
         "CREATE TABLE IF NOT EXISTS NoteHasSource (
             note_id INTEGER,
             source_id INTEGER,
+            cited INTEGER NOT NULL DEFAULT 0,
             FOREIGN KEY (note_id) REFERENCES Note(id),
             FOREIGN KEY (source_id) REFERENCES Source(id)
         )",
@@ -124,6 +227,32 @@ pub fn create_db_tables(db_path: &Path) -> Result<(), Box<dyn std::error::Error>
             FOREIGN KEY (task_id) REFERENCES Task(id),
             FOREIGN KEY (project_id) REFERENCES Project(id)
         )",
+        "CREATE TABLE IF NOT EXISTS TaskDependsOnTask (
+            task_id INTEGER,
+            depends_on_id INTEGER,
+            FOREIGN KEY (task_id) REFERENCES Task(id),
+            FOREIGN KEY (depends_on_id) REFERENCES Task(id)
+        )",
+        "CREATE TABLE IF NOT EXISTS TaskHasContext (
+            task_id INTEGER,
+            context_id INTEGER,
+            FOREIGN KEY (task_id) REFERENCES Task(id),
+            FOREIGN KEY (context_id) REFERENCES Context(id)
+        )",
+        "CREATE TABLE IF NOT EXISTS TaskAnnotation (
+            id INTEGER PRIMARY KEY,
+            task_id INTEGER NOT NULL,
+            entry TEXT,
+            description TEXT NOT NULL,
+            FOREIGN KEY (task_id) REFERENCES Task(id)
+        )",
+        "CREATE TABLE IF NOT EXISTS TimeLog (
+            id INTEGER PRIMARY KEY,
+            task_id INTEGER NOT NULL,
+            start TEXT NOT NULL,
+            end TEXT,
+            FOREIGN KEY (task_id) REFERENCES Task(id)
+        )",
         "CREATE TABLE IF NOT EXISTS IdeaHasTopic (
             idea_id INTEGER,
             topic_id INTEGER,
@@ -145,9 +274,30 @@ pub fn create_db_tables(db_path: &Path) -> Result<(), Box<dyn std::error::Error>
         "CREATE TABLE IF NOT EXISTS WritingHasNote (
             writing_id INTEGER,
             note_id INTEGER,
+            position INTEGER,
             FOREIGN KEY (writing_id) REFERENCES Writing(id),
             FOREIGN KEY (note_id) REFERENCES Note(id)
         )",
+        "CREATE TABLE IF NOT EXISTS WritingSnapshot (
+            id INTEGER PRIMARY KEY,
+            writing_id INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            words INTEGER NOT NULL,
+            UNIQUE (writing_id, date),
+            FOREIGN KEY (writing_id) REFERENCES Writing(id)
+        )",
+        "CREATE TABLE IF NOT EXISTS WritingHasTopic (
+            writing_id INTEGER,
+            topic_id INTEGER,
+            FOREIGN KEY (writing_id) REFERENCES Writing(id),
+            FOREIGN KEY (topic_id) REFERENCES Topic(id)
+        )",
+        "CREATE TABLE IF NOT EXISTS WritingHasContext (
+            writing_id INTEGER,
+            context_id INTEGER,
+            FOREIGN KEY (writing_id) REFERENCES Writing(id),
+            FOREIGN KEY (context_id) REFERENCES Context(id)
+        )",
         "CREATE TABLE IF NOT EXISTS CodeHasProject (
             code_id INTEGER,
             project_id INTEGER,
@@ -168,11 +318,9 @@ pub fn create_db_tables(db_path: &Path) -> Result<(), Box<dyn std::error::Error>
         )",
     ];
 
-    let conn = Connection::open(db_path)?;
     for statement in &statements {
         if let Err(e) = conn.execute(statement, []) {
             eprintln!("Error executing statement: {}\nError: {}", statement, e);
-            fs::remove_file(db_path)?;
             return Err(Box::new(e));
         }
     }
@@ -180,65 +328,231 @@ pub fn create_db_tables(db_path: &Path) -> Result<(), Box<dyn std::error::Error>
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rusqlite::Connection;
-    use std::env;
+// Tasks only had a name and a project
+fn migrate_task_status(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(
+        conn,
+        "Task",
+        "status",
+        "CHAR(10) NOT NULL DEFAULT 'pending'",
+    )?;
+    add_column(conn, "Task", "priority", "CHAR(1)")?;
+    add_column(conn, "Task", "due", "TEXT")?;
+    // NOT NULL columns can only be added with a default, existing tasks get the current time
+    for column in ["entry", "modified"] {
+        if add_column(conn, "Task", column, "TEXT NOT NULL DEFAULT ''")? {
+            conn.execute(
+                &format!("UPDATE Task SET {} = ?", column),
+                params![get_timestamp()],
+            )?;
+        }
+    }
+    add_column(conn, "Task", "end", "TEXT")?;
 
-    #[test]
-    fn test_if_db_tables() {
-        let args: Vec<String> = env::args().collect();
-        println!("{:?}", args);
-        let conn = Connection::open(args[args.len() - 1].clone())
-            .expect("Failed to open in-memory database");
-        let result = create_db_tables(&conn);
-        assert!(result.is_ok());
+    // projects are linked through TaskHasProject now
+    if has_column(conn, "Task", "project_id")? {
+        conn.execute(
+            "INSERT INTO TaskHasProject (task_id, project_id)
+            SELECT id, project_id FROM Task
+            WHERE project_id IS NOT NULL AND NOT EXISTS (
+                SELECT 1 FROM TaskHasProject
+                WHERE task_id = Task.id AND project_id = Task.project_id
+            )",
+            [],
+        )?;
+    }
 
-        // Verify that tables were created
-        let tables = [
-            "Note",
-            "Topic",
-            "Context",
-            "Source",
-            "Project",
-            "Task",
-            "Idea",
-            "Writing",
-            "Code",
-            "CodeLanguage",
-            "NoteHasTopic",
-            "NoteHasContext",
-            "NoteHasSource",
-            "TopicHasTopic",
-            "ContextHasContext",
-            "ProjectHasTopic",
-            "ProjectHasIdea",
-            "TaskHasProject",
-            "IdeaHasTopic",
-            "IdeaHasTask",
-            "WritingHasProject",
-            "WritingHasNote",
-            "CodeHasProject",
-            "CodeHasNote",
-            "CodeHasLanguage",
-        ];
+    Ok(())
+}
 
-        for table in &tables {
-            let query = format!(
-                "SELECT name FROM sqlite_master WHERE type='table' AND name='{}';",
-                table
-            );
-            let mut stmt = conn.prepare(&query).expect("Failed to prepare statement");
-            let table_exists: Option<String> = stmt.query_row([], |row| row.get(0)).ok();
-            assert_eq!(
-                table_exists,
-                Some(table.to_string()),
-                "Table {} does not exist",
+fn migrate_task_recurrence(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(conn, "Task", "recur", "TEXT")?;
+    add_column(conn, "Task", "parent_id", "INTEGER REFERENCES Task(id)")?;
+
+    Ok(())
+}
+
+fn migrate_project_status(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(
+        conn,
+        "Project",
+        "status",
+        "CHAR(10) NOT NULL DEFAULT 'active'",
+    )?;
+    add_column(conn, "Project", "start", "TEXT")?;
+    add_column(conn, "Project", "target", "TEXT")?;
+    add_column(
+        conn,
+        "Project",
+        "parent_project_id",
+        "INTEGER REFERENCES Project(id)",
+    )?;
+
+    Ok(())
+}
+
+fn migrate_task_position(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(conn, "Task", "position", "INTEGER")?;
+
+    Ok(())
+}
+
+// UNIQUE columns can't be added, the index takes their place
+fn migrate_task_uuid(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(conn, "Task", "uuid", "TEXT")?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS TaskUuid ON Task (uuid)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn migrate_task_dates(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    for column in ["scheduled", "wait", "until"] {
+        add_column(conn, "Task", column, "TEXT")?;
+    }
+
+    Ok(())
+}
+
+fn migrate_idea_stage(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(conn, "Idea", "stage", "CHAR(10) NOT NULL DEFAULT 'seed'")?;
+
+    Ok(())
+}
+
+// Writings and journal entries are files now, older writings keep a NULL path
+fn migrate_writing_files(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(
+        conn,
+        "Writing",
+        "kind",
+        "CHAR(10) NOT NULL DEFAULT 'writing'",
+    )?;
+    add_column(conn, "Writing", "path", "TEXT")?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS WritingPath ON Writing (path)",
+        [],
+    )?;
+    add_column(conn, "Writing", "date", "TEXT")?;
+    add_column(conn, "Writing", "words", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column(conn, "Writing", "modified", "TEXT")?;
+
+    Ok(())
+}
+
+fn migrate_writing_goals(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(conn, "Writing", "goal", "INTEGER")?;
+    add_column(conn, "Writing", "daily_goal", "INTEGER")?;
+
+    Ok(())
+}
+
+fn migrate_writing_note_position(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(conn, "WritingHasNote", "position", "INTEGER")?;
+
+    Ok(())
+}
+
+// Snippets are files now and every language is only stored once, duplicates are merged into the
+// first language of the same name
+fn migrate_code_files(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(conn, "Code", "path", "TEXT")?;
+
+    for table in ["Code", "CodeHasLanguage"] {
+        conn.execute(
+            &format!(
+                "UPDATE {0} SET language_id = (
+                    SELECT MIN(first.id) FROM CodeLanguage AS first
+                    JOIN CodeLanguage AS duplicate ON duplicate.name = first.name
+                    WHERE duplicate.id = {0}.language_id
+                )
+                WHERE language_id IN (SELECT id FROM CodeLanguage)",
                 table
-            );
-        }
+            ),
+            [],
+        )?;
+    }
+    conn.execute(
+        "DELETE FROM CodeLanguage
+        WHERE id NOT IN (SELECT MIN(id) FROM CodeLanguage GROUP BY name)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS CodeLanguageName ON CodeLanguage (name)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn migrate_code_hash(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(conn, "Code", "hash", "TEXT")?;
+
+    Ok(())
+}
+
+fn migrate_code_sources(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(conn, "Code", "source_path", "TEXT")?;
+    add_column(conn, "Code", "line_start", "INTEGER")?;
+    add_column(conn, "Code", "line_end", "INTEGER")?;
+
+    Ok(())
+}
+
+fn migrate_source_references(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(conn, "Source", "type", "CHAR(20)")?;
+    add_column(conn, "Source", "authors", "TEXT")?;
+    add_column(conn, "Source", "year", "CHAR(10)")?;
+    for column in ["title", "publisher", "doi", "url", "citation_key"] {
+        add_column(conn, "Source", column, "TEXT")?;
     }
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS SourceCitationKey ON Source (citation_key)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// Links that come from a citation in the note are recomputed whenever the note changes, existing
+// links came from tags and attachments and are kept
+fn migrate_note_citations(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    add_column(conn, "NoteHasSource", "cited", "INTEGER NOT NULL DEFAULT 0")?;
+
+    Ok(())
+}
+
+// Returns false if the column already exists, e.g. in a database created while it was added
+fn add_column(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if has_column(conn, table, column)? {
+        return Ok(false);
+    }
+    conn.execute(
+        &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+        [],
+    )?;
+
+    Ok(true)
+}
+
+fn has_column(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<usize, String>(1))?
+        .collect::<Result<Vec<String>, _>>()?;
+
+    Ok(columns.iter().any(|name| name == column))
 }
 
 //pub fn get_id_or_create(
@@ -300,6 +614,60 @@ pub fn get_parents(tag: &str) -> Result<(Vec<&str>, &str), Box<dyn std::error::E
     Ok((parts, child.unwrap()))
 }
 
+// All Markdown files below a directory, sorted by path
+pub fn get_markdown_files(
+    directory: &Path,
+) -> Result<Vec<std::path::PathBuf>, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(get_markdown_files(&path)?);
+        } else if path.extension().is_some_and(|extension| extension == "md") {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+// Full name of a topic or project including its parents, e.g. science/physics. The path starts
+// at a NULL parent or at root, and an entry that is its own ancestor ends it instead of looping
+// forever. The table and column are never user input.
+pub fn get_path(
+    conn: &Connection,
+    table: &str,
+    parent_column: &str,
+    root: Option<i64>,
+    id: i64,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let query = format!("SELECT name, {} FROM {} WHERE id = ?", parent_column, table);
+    let mut names = Vec::new();
+    let mut visited = HashSet::new();
+    let mut next = Some(id);
+
+    while let Some(id) = next.filter(|id| visited.insert(*id)) {
+        let (name, parent_id): (String, Option<i64>) =
+            conn.query_row(&query, params![id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        names.push(name);
+        next = parent_id.filter(|parent_id| Some(*parent_id) != root);
+    }
+    names.reverse();
+
+    Ok(names.join("/"))
+}
+
+// File name for an entry, "On Writing Well" becomes on-writing-well
+pub fn slugify(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+}
+
 pub fn get_content_by_id(
     conn: &Connection,
     table: &str,
@@ -314,6 +682,21 @@ pub fn get_content_by_id(
     Ok(content)
 }
 
+pub fn get_timestamp() -> String {
+    Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+pub fn get_date() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
+}
+
+pub fn parse_date(value: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}'. Use the format YYYY-MM-DD.", value))?;
+
+    Ok(date.format("%Y-%m-%d").to_string())
+}
+
 pub fn get_tags(elements: &[String]) -> HashMap<String, String> {
     let mut hashmap = HashMap::new();
 
@@ -322,12 +705,187 @@ pub fn get_tags(elements: &[String]) -> HashMap<String, String> {
         std::process::exit(1);
     }
 
+    // only the first colon separates, so values like url:https://example.com stay intact
     for element in elements {
-        let parts: Vec<&str> = element.split(':').collect();
-        if parts.len() == 2 {
-            hashmap.insert(parts[0].to_string(), parts[1].to_string());
+        if let Some((key, value)) = element.split_once(':') {
+            hashmap.insert(key.to_string(), value.to_string());
         }
     }
 
     hashmap
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+    use std::env;
+
+    #[test]
+    fn test_if_db_tables() {
+        let db_path = env::temp_dir().join(format!("cerebra-test-{}.db", std::process::id()));
+        let result = create_db_tables(&db_path);
+        assert!(result.is_ok());
+
+        let conn = Connection::open(&db_path).expect("Failed to open test database");
+
+        // Verify that tables were created
+        let tables = [
+            "Note",
+            "Topic",
+            "Context",
+            "Source",
+            "Attachment",
+            "Project",
+            "Task",
+            "Idea",
+            "Writing",
+            "Code",
+            "CodeLanguage",
+            "NoteHasTopic",
+            "NoteHasContext",
+            "NoteHasSource",
+            "TopicHasTopic",
+            "ContextHasContext",
+            "ProjectHasTopic",
+            "ProjectHasIdea",
+            "TaskHasProject",
+            "TaskDependsOnTask",
+            "TaskHasContext",
+            "TaskAnnotation",
+            "TimeLog",
+            "IdeaHasTopic",
+            "IdeaHasTask",
+            "WritingHasProject",
+            "WritingHasNote",
+            "WritingHasTopic",
+            "WritingSnapshot",
+            "WritingHasContext",
+            "CodeHasProject",
+            "CodeHasNote",
+            "CodeHasLanguage",
+        ];
+
+        for table in &tables {
+            let query = format!(
+                "SELECT name FROM sqlite_master WHERE type='table' AND name='{}';",
+                table
+            );
+            let mut stmt = conn.prepare(&query).expect("Failed to prepare statement");
+            let table_exists: Option<String> = stmt.query_row([], |row| row.get(0)).ok();
+            assert_eq!(
+                table_exists,
+                Some(table.to_string()),
+                "Table {} does not exist",
+                table
+            );
+        }
+
+        drop(conn);
+        fs::remove_file(&db_path).expect("Failed to remove test database");
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        // tables as they were created before the first migration
+        conn.execute_batch(
+            "CREATE TABLE Task (
+                id INTEGER PRIMARY KEY,
+                name CHAR(50) NOT NULL,
+                project_id INTEGER
+            );
+            CREATE TABLE TaskHasProject (task_id INTEGER, project_id INTEGER);
+            CREATE TABLE Project (id INTEGER PRIMARY KEY, name CHAR(50) NOT NULL);
+            CREATE TABLE Idea (id INTEGER PRIMARY KEY, name CHAR(50) NOT NULL);
+            CREATE TABLE Source (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+            CREATE TABLE NoteHasSource (note_id INTEGER, source_id INTEGER);
+            CREATE TABLE Writing (id INTEGER PRIMARY KEY, name CHAR(50) NOT NULL);
+            CREATE TABLE WritingHasNote (writing_id INTEGER, note_id INTEGER);
+            CREATE TABLE Code (
+                id INTEGER PRIMARY KEY,
+                name CHAR(50) NOT NULL,
+                language_id INTEGER NOT NULL
+            );
+            CREATE TABLE CodeLanguage (id INTEGER PRIMARY KEY, name CHAR(50) NOT NULL);
+            CREATE TABLE CodeHasLanguage (code_id INTEGER, language_id INTEGER);
+            INSERT INTO CodeLanguage (name) VALUES ('rust'), ('python'), ('rust');
+            INSERT INTO Code (name, language_id) VALUES ('main', 3);
+            INSERT INTO CodeHasLanguage (code_id, language_id) VALUES (1, 3);
+            INSERT INTO Project (name) VALUES ('cerebra');
+            INSERT INTO Task (name, project_id) VALUES ('write tests', 3);",
+        )
+        .unwrap();
+
+        migrate(&mut conn).unwrap();
+        // a second run doesn't do anything
+        migrate(&mut conn).unwrap();
+
+        let (status, entry): (String, String) = conn
+            .query_row("SELECT status, entry FROM Task WHERE id = 1", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(status, "pending");
+        assert!(!entry.is_empty());
+        let links: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM TaskHasProject WHERE task_id = 1 AND project_id = 3",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(links, 1);
+        for column in ["recur", "parent_id", "position", "uuid", "until"] {
+            assert!(has_column(&conn, "Task", column).unwrap());
+        }
+        let status: String = conn
+            .query_row("SELECT status FROM Project WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(status, "active");
+        assert!(has_column(&conn, "Idea", "stage").unwrap());
+        assert!(has_column(&conn, "WritingHasNote", "position").unwrap());
+        assert!(has_column(&conn, "NoteHasSource", "cited").unwrap());
+        for column in ["type", "authors", "doi", "citation_key"] {
+            assert!(has_column(&conn, "Source", column).unwrap());
+        }
+        for column in ["path", "hash", "source_path", "line_end"] {
+            assert!(has_column(&conn, "Code", column).unwrap());
+        }
+        let language_ids: (i64, i64, i64) = conn
+            .query_row(
+                "SELECT Code.language_id, CodeHasLanguage.language_id,
+                    (SELECT COUNT(*) FROM CodeLanguage)
+                FROM Code JOIN CodeHasLanguage ON CodeHasLanguage.code_id = Code.id",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(language_ids, (1, 1, 2));
+        for column in ["kind", "path", "date", "words", "modified", "daily_goal"] {
+            assert!(has_column(&conn, "Writing", column).unwrap());
+        }
+        let version: i64 = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version as usize, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("On Writing Well!"), "on-writing-well");
+    }
+
+    #[test]
+    fn test_get_tags() {
+        let tags = get_tags(&[
+            "url:https://example.com/a:b".to_string(),
+            "topic:science/physics".to_string(),
+        ]);
+
+        assert_eq!(tags["url"], "https://example.com/a:b");
+        assert_eq!(tags["topic"], "science/physics");
+    }
+}
@@ -1,10 +1,17 @@
+pub mod attachment;
+pub mod bibtex;
+pub mod citation;
 pub mod code;
 pub mod context;
+pub mod dependency;
 pub mod idea;
 pub mod init;
 pub mod note;
 pub mod project;
 pub mod source;
 pub mod task;
+pub mod taskwarrior;
+pub mod timelog;
+pub mod todo;
 pub mod topic;
 pub mod writing;
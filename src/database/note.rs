@@ -3,9 +3,9 @@ use fallible_iterator::FallibleIterator;
 use rusqlite::{params, Connection};
 use std::collections::HashMap;
 use std::error::Error;
-use std::fs::{File, OpenOptions};
-use std::io::{self, BufRead, BufReader, Write};
-use std::path::Path;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 pub fn add(
     note_directory: &Path,
@@ -13,29 +13,64 @@ pub fn add(
     content: &str,
     tags: HashMap<String, String>,
 ) -> Result<(), Box<dyn Error>> {
+    // notes are stored without surrounding whitespace, the same way they are read back from files
+    let content = content.trim();
+    let (id, topic) = add_entry(conn, content, tags)?;
+    add_file(note_directory, conn, id, content, &topic)
+}
+
+// Only stores the note in the database and returns its id and topic, so the note file can be
+// written once a transaction is committed
+pub fn add_entry(
+    conn: &Connection,
+    content: &str,
+    tags: HashMap<String, String>,
+) -> Result<(i64, String), Box<dyn Error>> {
     let mut source = String::new();
     let mut topic = String::new();
     let mut context = String::new();
+    let mut project = String::new();
 
     for (key, value) in &tags {
         match key.as_str() {
             "source" => source = value.parse().unwrap(),
             "topic" => topic = value.parse().unwrap(),
             "context" => context = value.parse().unwrap(),
+            "project" => project = value.parse().unwrap(),
             _ => panic!("Invalid key in tags"),
         }
     }
 
     let last_id = add_to_db(conn, content, &source, &topic, &context)
         .expect("Failed to add note to database");
+
+    if !project.is_empty() {
+        let (project_parents, project_child) =
+            database::init::get_parents(&project).expect("Failed to get project parents");
+        let project_id = database::project::get_id(conn, project_child, project_parents)
+            .expect("Failed to get project id");
+        conn.execute(
+            "UPDATE note SET project_id = ?1 WHERE id = ?2",
+            params![project_id, last_id],
+        )?;
+    }
     println!(
         "Added note {}: '{}' with source '{}', topic '{}', context '{}'",
         last_id, content, source, topic, context
     );
 
-    add_to_notes(note_directory, content, &topic).expect("Failed to add note to notes");
+    Ok((last_id, topic))
+}
 
-    Ok(())
+pub fn add_file(
+    note_directory: &Path,
+    conn: &Connection,
+    id: i64,
+    content: &str,
+    topic: &str,
+) -> Result<(), Box<dyn Error>> {
+    add_to_notes(note_directory, id, content, topic).expect("Failed to add note to notes");
+    link_citations(conn, id, content)
 }
 
 fn add_to_db(
@@ -45,16 +80,16 @@ fn add_to_db(
     topic: &str,
     context: &str,
 ) -> Result<i64, Box<dyn Error>> {
-    let source_id = database::source::get_id(&conn, &source).expect("Failed to get source id");
+    let source_id = database::source::get_id(conn, source).expect("Failed to get source id");
 
     let (topic_parents, topic_child) =
-        database::init::get_parents(&topic).expect("Failed to get topic parents");
-    let topic_id = database::topic::get_id(&conn, &topic_child, topic_parents)
-        .expect("Failed to get topic id");
+        database::init::get_parents(topic).expect("Failed to get topic parents");
+    let topic_id =
+        database::topic::get_id(conn, topic_child, topic_parents).expect("Failed to get topic id");
 
     let (context_parents, context_child) =
-        database::init::get_parents(&context).expect("Failed to get context parents");
-    let context_id = database::context::get_id(&conn, &context_child, context_parents)
+        database::init::get_parents(context).expect("Failed to get context parents");
+    let context_id = database::context::get_id(conn, context_child, context_parents)
         .expect("Failed to get context id");
 
     let source_id_str: &str = &source_id.to_string();
@@ -62,69 +97,75 @@ fn add_to_db(
     let topic_id_str: &str = &topic_id.to_string();
     conn.execute(
         "INSERT INTO note (content, source_id, context_id, topic_id) VALUES (?1, ?2, ?3, ?4)",
-        &[&content, source_id_str, context_id_str, &topic_id_str],
+        [content, source_id_str, context_id_str, topic_id_str],
     )
     .expect("Failed to insert note");
 
     Ok(conn.last_insert_rowid())
 }
 
-fn add_to_notes(note_directory: &Path, content: &str, topic: &str) -> Result<(), Box<dyn Error>> {
-    let (mut topic_parents, topic_child) = database::init::get_parents(&topic)?;
+// Notes are written to note_path/<topic parents>/<last parent>.md under a heading for the last
+// part of the topic, the newest note first. Every note is wrapped in anchors with its id, so it
+// can be found again wherever the file is moved to.
+fn add_to_notes(
+    note_directory: &Path,
+    id: i64,
+    content: &str,
+    topic: &str,
+) -> Result<(), Box<dyn Error>> {
+    let (mut topic_parents, topic_child) = database::init::get_parents(topic)?;
 
     let mut current_path = note_directory.to_path_buf();
     let file_name = topic_parents.pop().unwrap_or(topic_child);
     for parent in topic_parents {
         current_path.push(parent);
         if !current_path.exists() {
-            std::fs::create_dir(&current_path)?;
+            fs::create_dir(&current_path)?;
         }
     }
 
     current_path.push(format!("{}.md", file_name));
-    let file_exists = current_path.exists();
-    OpenOptions::new()
-        .create(true)
-        .read(true)
-        .write(true)
-        .open(&current_path)?;
-
-    let reader = BufReader::new(File::open(&current_path)?);
-    let mut lines = Vec::new();
-    let mut topic_found = false;
-
-    if !file_exists {
-        lines.push(format!("# {}\n", file_name));
-    }
-
-    if file_name == topic_child {
-        lines.push(format!("## {}\n\n{}", topic_child, content));
-        topic_found = true;
+    let mut lines: Vec<String> = if current_path.exists() {
+        fs::read_to_string(&current_path)?
+            .lines()
+            .map(String::from)
+            .collect()
     } else {
-        for line in reader.lines() {
-            let line = line?;
-            lines.push(line.clone());
-            if line.contains(&format!("## {}", topic_child)) {
-                topic_found = true;
-                lines.push(content.to_string());
+        vec![format!("# {}", file_name), String::new()]
+    };
+
+    let heading = format!("## {}", topic_child);
+    let block = get_block(id, content);
+    match lines.iter().position(|line| line.trim() == heading) {
+        Some(index) => {
+            lines.splice(
+                index + 1..index + 1,
+                std::iter::once(String::new()).chain(block),
+            );
+        }
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
             }
+            lines.push(heading);
+            lines.push(String::new());
+            lines.extend(block);
         }
     }
 
-    if !topic_found {
-        lines.push(format!("## {}\n\n{}", topic_child, content));
-    }
+    write_lines(&current_path, &lines)?;
 
-    // Reopen the file in write mode to overwrite the content
-    let mut file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(&current_path)?;
+    Ok(())
+}
 
-    for line in lines {
-        writeln!(file, "{}", line)?;
+// Records the sources cited in a note
+fn link_citations(conn: &Connection, id: i64, content: &str) -> Result<(), Box<dyn Error>> {
+    for key in database::citation::link(conn, id, content)? {
+        eprintln!(
+            "Citation key '{}' in note {} does not belong to any source",
+            key, id
+        );
     }
-
     Ok(())
 }
 
@@ -155,41 +196,47 @@ pub fn remove(note_directory: &Path, conn: &Connection, id: u64) -> Result<(), B
         return Ok(());
     }
 
+    // a note that can't be told apart from another one is refused before anything is deleted
+    let content = entry_content.unwrap_or("".to_string());
+    let block = find_note(note_directory, id as i64, &content)?;
+
+    for table in [
+        "NoteHasTopic",
+        "NoteHasContext",
+        "NoteHasSource",
+        "WritingHasNote",
+        "CodeHasNote",
+    ] {
+        conn.execute(
+            &format!("DELETE FROM {} WHERE note_id = ?", table),
+            params![id],
+        )?;
+    }
     let delete_query = "DELETE FROM note WHERE id = ?";
     conn.execute(delete_query, params![id])?;
 
-    let content = entry_content.unwrap_or("".to_string());
-    let mut found = false;
-    while !found {
-        for entry in std::fs::read_dir(note_directory)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_file() {
-                remove_line_from_file(&path, &content)?;
-                found = true;
-            }
-        }
+    match block {
+        Some(block) => remove_from_notes(block)?,
+        None => eprintln!("Note {} was not found in the notes directory", id),
     }
 
+    println!("Deleted note {}", id);
     Ok(())
 }
 
-fn remove_line_from_file<P: AsRef<Path>>(file_path: P, line_content: &str) -> io::Result<()> {
-    let file = File::open(&file_path)?;
-    let reader = BufReader::new(file);
-    let mut lines: Vec<String> = Vec::new();
+// Removes the block of a note from its file
+fn remove_from_notes(block: NoteBlock) -> Result<(), Box<dyn Error>> {
+    let (file, mut lines, start, end) = block;
 
-    for line in reader.lines() {
-        let line = line?;
-        if !line.contains(line_content) {
-            lines.push(line);
-        }
-    }
-
-    let mut file = File::create(&file_path)?;
-    for line in lines {
-        writeln!(file, "{}", line)?;
+    lines.drain(start..=end);
+    // the blank line in front of the note goes with it
+    if start > 0
+        && lines[start - 1].trim().is_empty()
+        && lines.get(start).is_none_or(|line| line.trim().is_empty())
+    {
+        lines.remove(start - 1);
     }
+    write_lines(&file, &lines)?;
 
     Ok(())
 }
@@ -198,8 +245,11 @@ pub fn modify(
     note_directory: &Path,
     conn: &Connection,
     id: u64,
-    tags: HashMap<String, String>,
+    mut tags: HashMap<String, String>,
 ) -> Result<(), Box<dyn Error>> {
+    if let Some(content) = tags.get_mut("content") {
+        *content = content.trim().to_string();
+    }
     let old_content: Option<String> = match conn.query_row(
         "SELECT content FROM note WHERE id = ?1",
         params![id.to_string()],
@@ -212,15 +262,26 @@ pub fn modify(
         }
     };
 
+    // the note is looked up first, so nothing is modified if it can't be found
+    let block = match tags.get("content") {
+        Some(_) => {
+            let old_content = old_content.unwrap_or("".to_string());
+            match find_note(note_directory, id as i64, &old_content)? {
+                Some(block) => Some(block),
+                None => {
+                    eprintln!("Failed to find note in notes directory");
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+
     modify_db(conn, id, tags.clone()).expect("Failed to modify database entry");
 
-    if tags.get("content").is_some() {
-        modify_notes(
-            note_directory,
-            &old_content.unwrap_or("".to_string()),
-            tags.get("content").map_or("", String::as_str),
-        )
-        .expect("Failed to modify notes entry");
+    if let (Some(block), Some(content)) = (block, tags.get("content")) {
+        modify_notes(block, id as i64, content).expect("Failed to modify notes entry");
+        link_citations(conn, id as i64, content)?;
     }
 
     println!("Modified entry {}", id);
@@ -272,18 +333,18 @@ fn modify_db(
     {
         if !source.is_empty() {
             updated_source_id =
-                database::source::get_id(&conn, &source).expect("Failed to get source id");
+                database::source::get_id(conn, &source).expect("Failed to get source id");
         }
         if !topic.is_empty() {
             let (topic_parents, topic_child) =
                 database::init::get_parents(&topic).expect("Failed to get topic parents");
-            updated_topic_id = database::topic::get_id(&conn, &topic_child, topic_parents)
+            updated_topic_id = database::topic::get_id(conn, topic_child, topic_parents)
                 .expect("Failed to get topic id");
         }
         if !context.is_empty() {
             let (context_parents, context_child) =
                 database::init::get_parents(&context).expect("Failed to get context parents");
-            updated_context_id = database::context::get_id(&conn, &context_child, context_parents)
+            updated_context_id = database::context::get_id(conn, context_child, context_parents)
                 .expect("Failed to get context id");
         }
         if !content.is_empty() {
@@ -311,51 +372,248 @@ fn modify_db(
     Ok(())
 }
 
-fn modify_notes(
+// Replaces the block of a note with its new content. A note written before anchors were added
+// gets them now.
+fn modify_notes(block: NoteBlock, id: i64, new_content: &str) -> Result<(), Box<dyn Error>> {
+    let (file, mut lines, start, end) = block;
+
+    lines.splice(start..=end, get_block(id, new_content));
+    write_lines(&file, &lines)?;
+
+    Ok(())
+}
+
+// File and line of the first line of a note. A note written before anchors were added gets them
+// now, so it can be read back after editing.
+pub fn locate(
     note_directory: &Path,
-    old_content: &str,
-    new_content: &str,
-) -> Result<(), Box<dyn Error>> {
-    let mut found = false;
-    for entry in std::fs::read_dir(note_directory)? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_file() {
-            modify_line_in_file(&path, old_content, new_content)?;
-            found = true;
+    id: i64,
+    content: &str,
+) -> Result<Option<(PathBuf, usize)>, Box<dyn Error>> {
+    let (file, mut lines, start, end) = match find_note(note_directory, id, content)? {
+        Some(block) => block,
+        None => return Ok(None),
+    };
+
+    if parse_anchor(&lines[start]) != Some(id) {
+        lines.splice(start..=end, get_block(id, content));
+        write_lines(&file, &lines)?;
+    }
+
+    Ok(Some((file, start + 2)))
+}
+
+// The content of a note as it is in the notes directory, without surrounding whitespace
+pub fn read_block(note_directory: &Path, id: i64) -> Result<Option<String>, Box<dyn Error>> {
+    for file in database::init::get_markdown_files(note_directory)? {
+        let lines: Vec<String> = fs::read_to_string(&file)?
+            .lines()
+            .map(String::from)
+            .collect();
+
+        if let Some((start, end)) = find_block(&lines, id) {
+            let content = lines[start + 1..end].join("\n").trim().to_string();
+            return Ok(Some(content));
         }
     }
 
-    if !found {
-        eprintln!("Failed to find note in notes directory");
+    Ok(None)
+}
+
+// Takes over the content of a note that was edited in its file
+pub fn set_content(conn: &Connection, id: i64, content: &str) -> Result<(), Box<dyn Error>> {
+    let content = content.trim();
+    conn.execute(
+        "UPDATE note SET content = ? WHERE id = ?",
+        params![content, id],
+    )?;
+    link_citations(conn, id, content)
+}
+
+fn get_block(id: i64, content: &str) -> Vec<String> {
+    std::iter::once(format!("<!-- cerebra:{} -->", id))
+        .chain(content.lines().map(String::from))
+        .chain(std::iter::once(format!("<!-- /cerebra:{} -->", id)))
+        .collect()
+}
+
+// The id of the note that starts on this line
+pub fn parse_anchor(line: &str) -> Option<i64> {
+    line.trim()
+        .strip_prefix("<!-- cerebra:")?
+        .strip_suffix("-->")?
+        .trim()
+        .parse()
+        .ok()
+}
+
+// First and last line of the block of a note, including its anchors
+fn find_block(lines: &[String], id: i64) -> Option<(usize, usize)> {
+    let start = lines
+        .iter()
+        .position(|line| parse_anchor(line) == Some(id))?;
+    let end_anchor = format!("<!-- /cerebra:{} -->", id);
+    let end = start
+        + lines[start..]
+            .iter()
+            .position(|line| line.trim() == end_anchor)?;
+
+    Some((start, end))
+}
+
+// File, lines and first and last line of the block of a note
+type NoteBlock = (PathBuf, Vec<String>, usize, usize);
+
+// Finds a note by its anchors. A note written before anchors were added is matched by its content
+// instead, which has to be on exactly one line of all files, otherwise the note could be
+// mistaken for another one.
+fn find_note(
+    note_directory: &Path,
+    id: i64,
+    content: &str,
+) -> Result<Option<NoteBlock>, Box<dyn Error>> {
+    let mut candidates = Vec::new();
+
+    for file in database::init::get_markdown_files(note_directory)? {
+        let lines: Vec<String> = fs::read_to_string(&file)?
+            .lines()
+            .map(String::from)
+            .collect();
+
+        if let Some((start, end)) = find_block(&lines, id) {
+            return Ok(Some((file, lines, start, end)));
+        }
+        for index in find_lines(&lines, content) {
+            candidates.push((file.clone(), lines.clone(), index, index));
+        }
+    }
+
+    if candidates.len() > 1 {
+        eprintln!(
+            "Note {} has no anchors and its content is on more than one line, add \
+            <!-- cerebra:{} --> and <!-- /cerebra:{} --> around the right one:",
+            id, id, id
+        );
+        for (file, _, index, _) in &candidates {
+            eprintln!("{}:{}", file.display(), index + 1);
+        }
         std::process::exit(1);
     }
 
-    Ok(())
+    Ok(candidates.pop())
 }
 
-fn modify_line_in_file<P: AsRef<Path>>(
-    file_path: P,
-    old_content: &str,
-    new_content: &str,
-) -> io::Result<()> {
-    let file = File::open(&file_path)?;
-    let reader = BufReader::new(file);
-    let mut lines: Vec<String> = Vec::new();
-
-    for line in reader.lines() {
-        let line = line?;
-        if line.contains(old_content) {
-            lines.push(new_content.to_string());
-        } else {
-            lines.push(line);
+// Lines with the content of a note without anchors, lines in the blocks of other notes are left
+// out
+fn find_lines(lines: &[String], content: &str) -> Vec<usize> {
+    let content = content.trim();
+    let mut indices = Vec::new();
+    if content.is_empty() {
+        return indices;
+    }
+
+    let mut in_block = false;
+    for (index, line) in lines.iter().enumerate() {
+        if parse_anchor(line).is_some() {
+            in_block = true;
+        } else if line.trim().starts_with("<!-- /cerebra:") {
+            in_block = false;
+        } else if !in_block && line.trim() == content {
+            indices.push(index);
         }
     }
 
-    let mut file = File::create(&file_path)?;
+    indices
+}
+
+fn write_lines(file: &Path, lines: &[String]) -> io::Result<()> {
+    let mut file = File::create(file)?;
     for line in lines {
         writeln!(file, "{}", line)?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_block() {
+        let lines: Vec<String> = "# physics\n\n## light\n\n<!-- cerebra:4 -->\nFast.\n\
+            <!-- /cerebra:4 -->\n\n<!-- cerebra:42 -->\nLight bends.\n\nReally.\n\
+            <!-- /cerebra:42 -->"
+            .lines()
+            .map(String::from)
+            .collect();
+
+        assert_eq!(find_block(&lines, 42), Some((8, 12)));
+        assert_eq!(find_block(&lines, 4), Some((4, 6)));
+        assert_eq!(find_block(&lines, 5), None);
+    }
+
+    #[test]
+    fn test_find_lines() {
+        let lines: Vec<String> = "# physics\n\nLight bends.\n\n<!-- cerebra:4 -->\nLight bends.\n\
+            <!-- /cerebra:4 -->\n\nLight bends.\n"
+            .lines()
+            .map(String::from)
+            .collect();
+
+        assert_eq!(find_lines(&lines, " Light bends. "), vec![2, 8]);
+        assert_eq!(find_lines(&lines, "Fast."), Vec::<usize>::new());
+        assert_eq!(find_lines(&lines, ""), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_edit_block() {
+        let directory =
+            std::env::temp_dir().join(format!("cerebra-test-notes-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("physics.md");
+        // a note written before anchors were added
+        fs::write(&file, "# physics\n\n## light\n\nLight bends.\n").unwrap();
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        database::init::migrate(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO Note (id, content, source_id, context_id, topic_id)
+            VALUES (7, 'Light bends.\n', 1, 1, 1)",
+            [],
+        )
+        .unwrap();
+
+        assert_eq!(
+            locate(&directory, 7, "Light bends.\n").unwrap(),
+            Some((file.clone(), 6))
+        );
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "# physics\n\n## light\n\n<!-- cerebra:7 -->\nLight bends.\n<!-- /cerebra:7 -->\n"
+        );
+        assert_eq!(
+            read_block(&directory, 7).unwrap(),
+            Some("Light bends.".to_string())
+        );
+
+        // the block as it is after editing it
+        let edited = fs::read_to_string(&file)
+            .unwrap()
+            .replace("Light bends.\n", "\nLight bends around stars.\n\n");
+        fs::write(&file, edited).unwrap();
+        let content = read_block(&directory, 7).unwrap().unwrap();
+        assert_eq!(content, "Light bends around stars.");
+        set_content(&conn, 7, &format!("{}\n", content)).unwrap();
+
+        let stored: String = conn
+            .query_row("SELECT content FROM Note WHERE id = 7", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(stored, "Light bends around stars.");
+        assert_eq!(read_block(&directory, 8).unwrap(), None);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
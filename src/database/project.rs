@@ -1,22 +1,387 @@
-use rusqlite::Connection;
+use crate::database;
+use rusqlite::{params, Connection, OptionalExtension};
 use std::collections::HashMap;
 use std::error::Error;
 
+const STATUSES: [&str; 4] = ["active", "paused", "done", "archived"];
+
+pub fn get_id(
+    conn: &Connection,
+    name: &str,
+    mut parents: Vec<&str>,
+) -> Result<i64, Box<dyn Error>> {
+    let next_parent = parents.pop();
+    let mut parent_project_id: Option<i64> = None;
+    if let Some(parent) = next_parent {
+        parent_project_id = Some(get_id(conn, parent, parents)?);
+    }
+
+    let query = "SELECT id FROM project WHERE name = ? AND parent_project_id IS ?";
+    let mut stmt = conn.prepare(query)?;
+    let id: Option<i64> = stmt
+        .query_row(params![name, parent_project_id], |row| row.get(0))
+        .unwrap_or(None);
+
+    match id {
+        Some(id) => Ok(id),
+        None => {
+            let query =
+                "INSERT INTO project (name, status, parent_project_id) VALUES (?, 'active', ?)";
+            conn.execute(query, params![name, parent_project_id])?;
+            let id: i64 = conn.last_insert_rowid();
+            Ok(id)
+        }
+    }
+}
+
+// Same as get_id, but never creates a project, e.g. for `project show work/client`
+pub fn find_id(conn: &Connection, path: &str) -> Result<Option<i64>, Box<dyn Error>> {
+    let (id, found) = find_deepest(conn, path)?;
+
+    Ok(id.filter(|_| found == path.split('/').count()))
+}
+
+// The deepest project of the path that exists already and how many parts of the path it covers,
+// e.g. work for work/new/client
+fn find_deepest(conn: &Connection, path: &str) -> Result<(Option<i64>, usize), Box<dyn Error>> {
+    let mut parent_project_id: Option<i64> = None;
+    let mut found = 0;
+
+    for name in path.split('/') {
+        let id = conn
+            .query_row(
+                "SELECT id FROM project WHERE name = ? AND parent_project_id IS ?",
+                params![name, parent_project_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match id {
+            Some(id) => parent_project_id = Some(id),
+            None => break,
+        }
+        found += 1;
+    }
+
+    Ok((parent_project_id, found))
+}
+
 pub fn add(
-    _conn: &Connection,
-    _content: &str,
-    _tags: HashMap<String, String>,
+    conn: &Connection,
+    content: &str,
+    tags: HashMap<String, String>,
 ) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+    let mut path = content.to_string();
+    let mut status = "active".to_string();
+    let mut start = String::new();
+    let mut target = String::new();
+    let mut topic = String::new();
+
+    for (key, value) in &tags {
+        match key.as_str() {
+            "parent" => path = format!("{}/{}", value, content),
+            "status" => status = parse_status(value)?,
+            "start" => start = database::init::parse_date(value)?,
+            "target" => target = database::init::parse_date(value)?,
+            "topic" => topic = value.to_string(),
+            _ => {
+                eprintln!(
+                    "Invalid tag '{}'. Use 'parent', 'status', 'start', 'target' or 'topic'.",
+                    key
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // adding a project again would reset its status and dates
+    if let Some(id) = find_id(conn, &path)? {
+        eprintln!(
+            "Project '{}' already exists with id {}. Use `cerebra mod project {}` to change it.",
+            path, id, id
+        );
+        std::process::exit(1);
+    }
+
+    let (parents, name) = database::init::get_parents(&path)?;
+    let id = get_id(conn, name, parents).expect("Failed to get project id");
+    conn.execute(
+        "UPDATE project SET status = ?, start = ?, target = ? WHERE id = ?",
+        params![
+            status,
+            Some(start.as_str()).filter(|start| !start.is_empty()),
+            Some(target.as_str()).filter(|target| !target.is_empty()),
+            id
+        ],
+    )?;
+
+    if !topic.is_empty() {
+        set_topic(conn, id, &topic)?;
+    }
+
+    println!(
+        "Added project {}: '{}' with status '{}', start '{}', target '{}', topic '{}'",
+        id, path, status, start, target, topic
+    );
+
     Ok(())
 }
 
-pub fn remove(_conn: &Connection, _id: u64) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+pub fn remove(conn: &Connection, id: u64) -> Result<(), Box<dyn Error>> {
+    let name = get_name(conn, id);
+
+    let mut input = String::new();
+    println!(
+        "Are you sure you want to delete project '{}'? Its tasks and sub-projects are kept. (y/n)",
+        name
+    );
+    std::io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+
+    if input.trim() != "y" {
+        println!("Aborting deletion");
+        return Ok(());
+    }
+
+    conn.execute(
+        "DELETE FROM TaskHasProject WHERE project_id = ?",
+        params![id],
+    )?;
+    conn.execute(
+        "DELETE FROM ProjectHasTopic WHERE project_id = ?",
+        params![id],
+    )?;
+    conn.execute(
+        "DELETE FROM ProjectHasIdea WHERE project_id = ?",
+        params![id],
+    )?;
+    conn.execute(
+        "DELETE FROM WritingHasProject WHERE project_id = ?",
+        params![id],
+    )?;
+    conn.execute(
+        "DELETE FROM CodeHasProject WHERE project_id = ?",
+        params![id],
+    )?;
+    for table in ["note", "idea", "writing", "code"] {
+        conn.execute(
+            &format!(
+                "UPDATE {} SET project_id = NULL WHERE project_id = ?",
+                table
+            ),
+            params![id],
+        )?;
+    }
+    conn.execute(
+        "UPDATE project SET parent_project_id = NULL WHERE parent_project_id = ?",
+        params![id],
+    )?;
+    conn.execute("DELETE FROM project WHERE id = ?", params![id])?;
+
+    println!("Deleted project {}", id);
     Ok(())
 }
 
-pub fn modify(_conn: &Connection, _tags: HashMap<String, String>) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+pub fn modify(
+    conn: &Connection,
+    id: u64,
+    tags: HashMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    get_name(conn, id);
+
+    if tags.is_empty() {
+        eprintln!("No tags provided for modification");
+        std::process::exit(1);
+    }
+
+    for (key, value) in &tags {
+        match key.as_str() {
+            "name" => {
+                conn.execute(
+                    "UPDATE project SET name = ? WHERE id = ?",
+                    params![value, id],
+                )?;
+            }
+            "status" => {
+                conn.execute(
+                    "UPDATE project SET status = ? WHERE id = ?",
+                    params![parse_status(value)?, id],
+                )?;
+            }
+            "start" | "target" => {
+                let date = if value.is_empty() {
+                    None
+                } else {
+                    Some(database::init::parse_date(value)?)
+                };
+                // key is one of the two column names matched above
+                let query = format!("UPDATE project SET {} = ? WHERE id = ?", key);
+                conn.execute(&query, params![date, id])?;
+            }
+            "parent" => {
+                // missing parts of the path are created below the deepest existing project, so
+                // that one is checked before anything is created
+                if find_deepest(conn, value)?
+                    .0
+                    .is_some_and(|parent_id| is_descendant(conn, parent_id, id as i64))
+                {
+                    return Err(format!(
+                        "Project {} cannot be moved below one of its own sub-projects",
+                        id
+                    )
+                    .into());
+                }
+                let parent_id = if value.is_empty() {
+                    None
+                } else {
+                    let (parents, name) = database::init::get_parents(value)?;
+                    Some(get_id(conn, name, parents)?)
+                };
+                conn.execute(
+                    "UPDATE project SET parent_project_id = ? WHERE id = ?",
+                    params![parent_id, id],
+                )?;
+            }
+            "topic" => {
+                conn.execute(
+                    "DELETE FROM ProjectHasTopic WHERE project_id = ?",
+                    params![id],
+                )?;
+                if !value.is_empty() {
+                    set_topic(conn, id as i64, value)?;
+                }
+            }
+            _ => {
+                eprintln!(
+                    "Invalid tag '{}'. Use 'name', 'status', 'start', 'target', 'parent' or 'topic'.",
+                    key
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    println!("Modified project {}", id);
     Ok(())
 }
+
+// Returns the project itself and all of its sub-projects, however deeply nested
+pub fn get_tree_ids(conn: &Connection, id: i64) -> Result<Vec<i64>, Box<dyn Error>> {
+    let query = "WITH RECURSIVE tree(id) AS (
+            SELECT ?
+            UNION
+            SELECT project.id FROM project JOIN tree ON project.parent_project_id = tree.id
+        )
+        SELECT id FROM tree";
+    let mut stmt = conn.prepare(query)?;
+    let ids: Vec<i64> = stmt
+        .query_map(params![id], |row| row.get(0))?
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(ids)
+}
+
+// Full name of a project including its parents, e.g. work/client
+pub fn get_path(conn: &Connection, id: i64) -> Result<String, Box<dyn Error>> {
+    database::init::get_path(conn, "project", "parent_project_id", None, id)
+}
+
+// Completed and total tasks of the given projects, ignoring deleted tasks and recurring templates
+pub fn get_progress(conn: &Connection, ids: &[i64]) -> Result<(i64, i64), Box<dyn Error>> {
+    let query = format!(
+        "SELECT COUNT(DISTINCT CASE WHEN task.status = 'done' THEN task.id END),
+            COUNT(DISTINCT task.id)
+        FROM task
+        JOIN TaskHasProject ON TaskHasProject.task_id = task.id
+        WHERE TaskHasProject.project_id IN ({})
+        AND task.status IN ('pending', 'done')",
+        join_ids(ids)
+    );
+    let progress = conn.query_row(&query, [], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    Ok(progress)
+}
+
+pub fn join_ids(ids: &[i64]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn is_descendant(conn: &Connection, id: i64, ancestor_id: i64) -> bool {
+    get_tree_ids(conn, ancestor_id)
+        .map(|ids| ids.contains(&id))
+        .unwrap_or(false)
+}
+
+fn set_topic(conn: &Connection, project_id: i64, topic: &str) -> Result<(), Box<dyn Error>> {
+    let (topic_parents, topic_child) = database::init::get_parents(topic)?;
+    let topic_id = database::topic::get_id(conn, topic_child, topic_parents)?;
+    conn.execute(
+        "INSERT INTO ProjectHasTopic (project_id, topic_id)
+        SELECT ?1, ?2 WHERE NOT EXISTS (
+            SELECT 1 FROM ProjectHasTopic WHERE project_id = ?1 AND topic_id = ?2
+        )",
+        params![project_id, topic_id],
+    )?;
+
+    Ok(())
+}
+
+fn get_name(conn: &Connection, id: u64) -> String {
+    let name: Option<String> = conn
+        .query_row(
+            "SELECT name FROM project WHERE id = ?",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()
+        .unwrap_or(None);
+
+    match name {
+        Some(name) => name,
+        None => {
+            eprintln!("Project {} does not exist", id);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_status(value: &str) -> Result<String, Box<dyn Error>> {
+    let status = value.to_lowercase();
+    if !STATUSES.contains(&status.as_str()) {
+        return Err(format!(
+            "Invalid status '{}'. Use 'active', 'paused', 'done' or 'archived'.",
+            value
+        )
+        .into());
+    }
+
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_deepest() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE Project (id INTEGER PRIMARY KEY, name TEXT, parent_project_id INTEGER);
+            INSERT INTO Project VALUES (1, 'work', NULL), (2, 'client', 1);",
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_deepest(&conn, "work/client/new").unwrap(),
+            (Some(2), 2)
+        );
+        assert_eq!(find_deepest(&conn, "home").unwrap(), (None, 0));
+        assert_eq!(find_id(&conn, "work/client").unwrap(), Some(2));
+        assert_eq!(find_id(&conn, "work/client/new").unwrap(), None);
+    }
+}
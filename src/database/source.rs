@@ -1,20 +1,318 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use std::collections::HashMap;
 use std::error::Error;
 
+// id, name, citation key, type, year and number of notes and attachments of a source
+pub type SourceRow = (
+    i64,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    i64,
+    i64,
+);
+
 pub fn get_id(conn: &Connection, name: &str) -> Result<i64, Box<dyn Error>> {
-    let query = "SELECT name FROM source WHERE name = ?";
-    let mut stmt = conn.prepare(&query).unwrap();
-    let id: Option<i64> = stmt
-        .query_row(params![name], |row| row.get(0))
-        .unwrap_or(None);
+    let id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM source WHERE name = ? ORDER BY id",
+            params![name],
+            |row| row.get(0),
+        )
+        .optional()?;
 
     match id {
         Some(id) => Ok(id),
         None => {
             let query = "INSERT INTO source (name) VALUES (?)";
-            conn.execute(&query, params![name])?;
+            conn.execute(query, params![name])?;
             let id: i64 = conn.last_insert_rowid();
             Ok(id)
         }
     }
 }
+
+pub fn modify(
+    conn: &Connection,
+    id: u64,
+    tags: HashMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    let exists: Option<i64> = conn
+        .query_row("SELECT id FROM source WHERE id = ?", params![id], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    if exists.is_none() {
+        eprintln!("Source {} does not exist", id);
+        std::process::exit(1);
+    }
+
+    if tags.is_empty() {
+        eprintln!("No tags provided for modification");
+        std::process::exit(1);
+    }
+
+    for (key, value) in &tags {
+        let column = match key.as_str() {
+            "name" => "name",
+            "type" => "type",
+            "author" => "authors",
+            "year" => "year",
+            "title" => "title",
+            "publisher" => "publisher",
+            "doi" => "doi",
+            "url" => "url",
+            "key" => "citation_key",
+            _ => {
+                eprintln!(
+                    "Invalid tag '{}'. Use 'name', 'type', 'author', 'year', 'title', 'publisher', 'doi', 'url' or 'key'.",
+                    key
+                );
+                std::process::exit(1);
+            }
+        };
+        // an empty value clears the field, except for the name every source needs
+        let value = if value.is_empty() && column != "name" {
+            None
+        } else {
+            Some(value)
+        };
+        conn.execute(
+            &format!("UPDATE source SET {} = ? WHERE id = ?", column),
+            params![value, id],
+        )?;
+    }
+
+    println!("Modified source {}", id);
+    Ok(())
+}
+
+// A source can be given by its id, its name or its citation key
+pub fn find_id(conn: &Connection, value: &str) -> Result<Option<i64>, Box<dyn Error>> {
+    Ok(conn
+        .query_row(
+            "SELECT id FROM source WHERE CAST(id AS TEXT) = ?1
+            UNION ALL SELECT id FROM source WHERE name = ?1
+            UNION ALL SELECT id FROM source WHERE citation_key = ?1
+            LIMIT 1",
+            params![value],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+pub fn get_name(conn: &Connection, id: i64) -> Result<String, Box<dyn Error>> {
+    Ok(
+        conn.query_row("SELECT name FROM source WHERE id = ?", params![id], |row| {
+            row.get(0)
+        })?,
+    )
+}
+
+// Notes without a source point to an unnamed one, which is left out
+pub fn list(conn: &Connection) -> Result<Vec<SourceRow>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT source.id, source.name, source.citation_key, source.type, source.year,
+            (SELECT COUNT(*) FROM note
+                WHERE note.source_id = source.id
+                OR note.id IN (SELECT note_id FROM NoteHasSource WHERE source_id = source.id)),
+            (SELECT COUNT(*) FROM attachment WHERE attachment.source_id = source.id)
+        FROM source
+        WHERE source.name != ''
+        ORDER BY source.name COLLATE NOCASE, source.id",
+    )?;
+    let sources: Vec<SourceRow> = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+            ))
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(sources)
+}
+
+pub fn rename(conn: &Connection, id: i64, name: &str) -> Result<(), Box<dyn Error>> {
+    if let Some(existing_id) = conn
+        .query_row(
+            "SELECT id FROM source WHERE name = ? AND id != ?",
+            params![name, id],
+            |row| row.get::<usize, i64>(0),
+        )
+        .optional()?
+    {
+        return Err(format!(
+            "Source {} is already called '{}', use `cerebra source merge {} {}` instead",
+            existing_id, name, id, existing_id
+        )
+        .into());
+    }
+
+    conn.execute("UPDATE source SET name = ? WHERE id = ?", params![name, id])?;
+
+    Ok(())
+}
+
+// Moves the notes, note links and attachments of one source to another and deletes it. Fields the
+// surviving source doesn't have yet, like a citation key, are taken over
+pub fn merge(conn: &Connection, from: i64, into: i64) -> Result<(), Box<dyn Error>> {
+    if from == into {
+        return Err("Cannot merge a source into itself".into());
+    }
+    // notes citing the key of the merged source would lose their source
+    if let (Some(from_key), Some(into_key)) =
+        (get_citation_key(conn, from)?, get_citation_key(conn, into)?)
+    {
+        return Err(format!(
+            "Sources {} and {} both have a citation key ('{}' and '{}'), cite one of them and clear the other with `cerebra mod source {} key:` first",
+            from, into, from_key, into_key, from
+        )
+        .into());
+    }
+
+    conn.execute(
+        "UPDATE note SET source_id = ? WHERE source_id = ?",
+        params![into, from],
+    )?;
+    // a note linked to both sources keeps the link if it wasn't only a citation of either
+    conn.execute(
+        "UPDATE NoteHasSource SET cited = 0 WHERE source_id = ?2 AND note_id IN (
+            SELECT note_id FROM NoteHasSource WHERE source_id = ?1 AND cited = 0
+        )",
+        params![from, into],
+    )?;
+    conn.execute(
+        "DELETE FROM NoteHasSource WHERE source_id = ?1 AND note_id IN (
+            SELECT note_id FROM NoteHasSource WHERE source_id = ?2
+        )",
+        params![from, into],
+    )?;
+    conn.execute(
+        "UPDATE NoteHasSource SET source_id = ? WHERE source_id = ?",
+        params![into, from],
+    )?;
+    // the same file attached to both sources is only kept once
+    conn.execute(
+        "DELETE FROM attachment WHERE source_id = ?1 AND hash IN (
+            SELECT hash FROM attachment WHERE source_id = ?2
+        )",
+        params![from, into],
+    )?;
+    conn.execute(
+        "UPDATE attachment SET source_id = ? WHERE source_id = ?",
+        params![into, from],
+    )?;
+
+    let fields: [Option<String>; 8] = conn.query_row(
+        "SELECT type, authors, year, title, publisher, doi, url, citation_key
+        FROM source WHERE id = ?",
+        params![from],
+        |row| {
+            Ok([
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+                row.get(7)?,
+            ])
+        },
+    )?;
+    // the citation key is unique, so the merged source has to be gone before it is taken over
+    conn.execute("DELETE FROM source WHERE id = ?", params![from])?;
+    conn.execute(
+        "UPDATE source SET type = COALESCE(type, ?1), authors = COALESCE(authors, ?2),
+            year = COALESCE(year, ?3), title = COALESCE(title, ?4),
+            publisher = COALESCE(publisher, ?5), doi = COALESCE(doi, ?6), url = COALESCE(url, ?7),
+            citation_key = COALESCE(citation_key, ?8)
+        WHERE id = ?9",
+        params![
+            fields[0], fields[1], fields[2], fields[3], fields[4], fields[5], fields[6], fields[7],
+            into
+        ],
+    )?;
+
+    Ok(())
+}
+
+fn get_citation_key(conn: &Connection, id: i64) -> Result<Option<String>, Box<dyn Error>> {
+    Ok(conn.query_row(
+        "SELECT citation_key FROM source WHERE id = ?",
+        params![id],
+        |row| row.get(0),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::init;
+
+    fn count(conn: &Connection, query: &str) -> i64 {
+        conn.query_row(query, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_find_rename_merge() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        init::migrate(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO Source (id, name, citation_key) VALUES
+                (1, 'Cosmos', NULL), (2, 'Cosmos (book)', 'sagan1980'), (3, '2', 'other');
+            INSERT INTO Note (id, content, source_id, context_id, topic_id) VALUES
+                (1, 'Stars', 1, 1, 1), (2, 'Planets', 2, 1, 1);
+            INSERT INTO NoteHasSource (note_id, source_id, cited) VALUES
+                (1, 1, 0), (1, 2, 1), (2, 2, 1);
+            INSERT INTO Attachment (source_id, path, original_name, size, mime, hash, added)
+            VALUES (1, 'a.pdf', 'a.pdf', 1, 'application/pdf', 'aaa', ''),
+                (2, 'a.pdf', 'copy.pdf', 1, 'application/pdf', 'aaa', ''),
+                (2, 'b.pdf', 'b.pdf', 1, 'application/pdf', 'bbb', '');",
+        )
+        .unwrap();
+
+        // ids come before names, names before citation keys
+        assert_eq!(find_id(&conn, "2").unwrap(), Some(2));
+        assert_eq!(find_id(&conn, "Cosmos").unwrap(), Some(1));
+        assert_eq!(find_id(&conn, "sagan1980").unwrap(), Some(2));
+        assert_eq!(find_id(&conn, "missing").unwrap(), None);
+
+        assert!(rename(&conn, 1, "Cosmos (book)").is_err());
+        rename(&conn, 1, "Cosmos: A Personal Voyage").unwrap();
+        assert_eq!(get_name(&conn, 1).unwrap(), "Cosmos: A Personal Voyage");
+
+        assert!(merge(&conn, 3, 2).is_err());
+        merge(&conn, 2, 1).unwrap();
+
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM Source WHERE id = 2"), 0);
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM Note WHERE source_id = 1"),
+            2
+        );
+        // note 1 was linked to both sources and keeps its uncited link once
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM NoteHasSource WHERE note_id = 1 AND source_id = 1 AND cited = 0"),
+            1
+        );
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM NoteHasSource"), 2);
+        // the copy of a.pdf is dropped, b.pdf moves over
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM Attachment WHERE source_id = 1"),
+            2
+        );
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM Attachment WHERE hash = 'aaa'"),
+            1
+        );
+        assert_eq!(find_id(&conn, "sagan1980").unwrap(), Some(1));
+    }
+}
@@ -1,22 +1,524 @@
-use rusqlite::Connection;
+use crate::database;
+use chrono::{Days, Months, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension};
 use std::collections::HashMap;
 use std::error::Error;
 
+const STATUSES: [&str; 3] = ["pending", "done", "deleted"];
+const PRIORITIES: [&str; 3] = ["H", "M", "L"];
+
+#[derive(Debug, Clone)]
+pub struct Task {
+    pub id: i64,
+    pub name: String,
+    pub priority: Option<String>,
+    pub due: Option<String>,
+    pub entry: String,
+    pub project: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Recurrence {
+    Days(u64),
+    Months(u32),
+}
+
+impl Recurrence {
+    // Counting from the start keeps monthly tasks on the 31st instead of drifting to the 28th
+    pub fn nth(&self, start: NaiveDate, n: u32) -> NaiveDate {
+        match self {
+            Recurrence::Days(days) => start + Days::new(days * u64::from(n)),
+            Recurrence::Months(months) => start + Months::new(months * n),
+        }
+    }
+}
+
 pub fn add(
-    _conn: &Connection,
-    _content: &str,
-    _tags: HashMap<String, String>,
+    conn: &Connection,
+    content: &str,
+    tags: HashMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    let mut project = String::new();
+    let mut due = String::new();
+    let mut priority = String::new();
+    let mut recur = String::new();
+    let mut depends = Vec::new();
+    let mut dates = Vec::new();
+
+    for (key, value) in &tags {
+        match key.as_str() {
+            "project" => project = value.to_string(),
+            "due" => due = database::init::parse_date(value)?,
+            "scheduled" | "wait" | "until" => {
+                dates.push((key.as_str(), database::init::parse_date(value)?))
+            }
+            "priority" => priority = parse_priority(value)?,
+            "recur" => {
+                parse_recurrence(value)?;
+                recur = value.to_string();
+            }
+            "depends" => depends = database::dependency::parse_depends(value)?,
+            _ => {
+                eprintln!(
+                    "Invalid tag '{}'. Use 'project', 'due', 'scheduled', 'wait', 'until', 'priority', 'recur' or 'depends'.",
+                    key
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if !recur.is_empty() && !depends.is_empty() {
+        eprintln!("Recurring tasks cannot depend on other tasks");
+        std::process::exit(1);
+    }
+
+    if recur.is_empty() {
+        // checked up front, so a missing task doesn't leave the new one behind
+        database::dependency::check_exists(conn, &depends)?;
+        let last_id = add_to_db(conn, content, &project, &due, &priority)
+            .expect("Failed to add task to database");
+        database::dependency::set(conn, last_id, &depends)?;
+        set_dates(conn, last_id, &dates)?;
+        println!(
+            "Added task {}: '{}' with project '{}', due '{}', priority '{}'",
+            last_id, content, project, due, priority
+        );
+    } else {
+        // recurring tasks start today unless told otherwise
+        if due.is_empty() {
+            due = database::init::get_date();
+        }
+
+        let template_id = add_template_to_db(conn, content, &project, &due, &priority, &recur)
+            .expect("Failed to add recurring task to database");
+        set_dates(conn, template_id, &dates)?;
+        println!(
+            "Added recurring task {}: '{}' with project '{}', due '{}', priority '{}', recur '{}'",
+            template_id, content, project, due, priority, recur
+        );
+
+        generate_recurring(conn)?;
+    }
+
+    Ok(())
+}
+
+pub fn add_to_db(
+    conn: &Connection,
+    content: &str,
+    project: &str,
+    due: &str,
+    priority: &str,
+) -> Result<i64, Box<dyn Error>> {
+    let timestamp = database::init::get_timestamp();
+    conn.execute(
+        "INSERT INTO task (name, status, priority, due, entry, modified)
+        VALUES (?1, 'pending', ?2, ?3, ?4, ?4)",
+        params![
+            content,
+            none_if_empty(priority),
+            none_if_empty(due),
+            timestamp
+        ],
+    )?;
+    let task_id = conn.last_insert_rowid();
+
+    if !project.is_empty() {
+        set_project(conn, task_id, project)?;
+    }
+
+    Ok(task_id)
+}
+
+// The template never shows up in reports, it only holds what its instances are created from
+fn add_template_to_db(
+    conn: &Connection,
+    content: &str,
+    project: &str,
+    due: &str,
+    priority: &str,
+    recur: &str,
+) -> Result<i64, Box<dyn Error>> {
+    let template_id = add_to_db(conn, content, project, due, priority)?;
+    conn.execute(
+        "UPDATE task SET status = 'recurring', recur = ? WHERE id = ?",
+        params![recur, template_id],
+    )?;
+
+    Ok(template_id)
+}
+
+// id, name, priority, due, scheduled, wait, until and recur of a recurring template
+type TemplateRow = (
+    i64,
+    String,
+    Option<String>,
+    String,
+    [Option<String>; 3],
+    String,
+);
+
+pub fn generate_recurring(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    let query = "SELECT id, name, priority, due, scheduled, wait, until, recur FROM task
+        WHERE status = 'recurring' AND NOT EXISTS (
+            SELECT 1 FROM task AS instance
+            WHERE instance.parent_id = task.id AND instance.status = 'pending'
+        )";
+    let mut stmt = conn.prepare(query)?;
+    let templates: Vec<TemplateRow> = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                [row.get(4)?, row.get(5)?, row.get(6)?],
+                row.get(7)?,
+            ))
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    for (template_id, name, priority, template_due, template_dates, recur) in templates {
+        // deleted and completed instances count as well, so every instance gets its own date
+        let count: u32 = conn.query_row(
+            "SELECT COUNT(*) FROM task WHERE parent_id = ?",
+            params![template_id],
+            |row| row.get(0),
+        )?;
+
+        let template_due = NaiveDate::parse_from_str(&template_due, "%Y-%m-%d")?;
+        let due = parse_recurrence(&recur)?.nth(template_due, count);
+        // scheduled, wait and until keep their distance to the due date
+        let [scheduled, wait, until] = template_dates.map(|date| {
+            date.and_then(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok())
+                .map(|date| (due + (date - template_due)).format("%Y-%m-%d").to_string())
+        });
+        let due = due.format("%Y-%m-%d").to_string();
+
+        let timestamp = database::init::get_timestamp();
+        conn.execute(
+            "INSERT INTO task
+                (name, status, priority, due, scheduled, wait, until, entry, modified, parent_id)
+            VALUES (?1, 'pending', ?2, ?3, ?4, ?5, ?6, ?7, ?7, ?8)",
+            params![
+                name,
+                priority,
+                due,
+                scheduled,
+                wait,
+                until,
+                timestamp,
+                template_id
+            ],
+        )?;
+        let task_id = conn.last_insert_rowid();
+
+        conn.execute(
+            "INSERT INTO TaskHasProject (task_id, project_id)
+            SELECT ?, project_id FROM TaskHasProject WHERE task_id = ?",
+            params![task_id, template_id],
+        )?;
+
+        println!(
+            "Created task {}: '{}' due '{}' from recurring task {}",
+            task_id, name, due, template_id
+        );
+    }
+
+    Ok(())
+}
+
+pub fn remove(conn: &Connection, id: u64) -> Result<(), Box<dyn Error>> {
+    let name = get_name(conn, id);
+
+    let mut input = String::new();
+    println!("Are you sure you want to delete task '{}'? (y/n)", name);
+    std::io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+
+    if input.trim() != "y" {
+        println!("Aborting deletion");
+        return Ok(());
+    }
+
+    set_status(conn, id, "deleted")?;
+    println!("Deleted task {}", id);
+
+    Ok(())
+}
+
+pub fn modify(
+    conn: &Connection,
+    id: u64,
+    tags: HashMap<String, String>,
 ) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+    // make sure the task exists before touching anything
+    get_name(conn, id);
+
+    if tags.is_empty() {
+        eprintln!("No tags provided for modification");
+        std::process::exit(1);
+    }
+
+    for (key, value) in &tags {
+        match key.as_str() {
+            "content" => {
+                conn.execute("UPDATE task SET name = ? WHERE id = ?", params![value, id])?;
+            }
+            "project" => {
+                conn.execute("DELETE FROM TaskHasProject WHERE task_id = ?", params![id])?;
+                if !value.is_empty() {
+                    set_project(conn, id as i64, value)?;
+                }
+            }
+            "due" | "scheduled" | "wait" | "until" => {
+                let date = if value.is_empty() {
+                    None
+                } else {
+                    Some(database::init::parse_date(value)?)
+                };
+                // key is one of the column names matched above
+                let query = format!("UPDATE task SET {} = ? WHERE id = ?", key);
+                conn.execute(&query, params![date, id])?;
+            }
+            "priority" => {
+                let priority = if value.is_empty() {
+                    None
+                } else {
+                    Some(parse_priority(value)?)
+                };
+                conn.execute(
+                    "UPDATE task SET priority = ? WHERE id = ?",
+                    params![priority, id],
+                )?;
+            }
+            "status" => set_status(conn, id, value)?,
+            "depends" => {
+                let depends = database::dependency::parse_depends(value)?;
+                database::dependency::set(conn, id as i64, &depends)?;
+            }
+            _ => {
+                eprintln!(
+                    "Invalid tag '{}'. Use 'content', 'project', 'due', 'scheduled', 'wait', 'until', 'priority', 'status' or 'depends'.",
+                    key
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    conn.execute(
+        "UPDATE task SET modified = ? WHERE id = ?",
+        params![database::init::get_timestamp(), id],
+    )?;
+
+    println!("Modified task {}", id);
+    Ok(())
+}
+
+// Tasks waiting until a later date are left out unless include_waiting is set
+pub fn get_pending(conn: &Connection, include_waiting: bool) -> Result<Vec<Task>, Box<dyn Error>> {
+    let query = "SELECT task.id, task.name, task.priority, task.due, task.entry, project.name
+        FROM task
+        LEFT JOIN TaskHasProject ON TaskHasProject.task_id = task.id
+        LEFT JOIN project ON project.id = TaskHasProject.project_id
+        WHERE task.status = 'pending' AND (?1 OR task.wait IS NULL OR task.wait <= ?2)
+        ORDER BY task.id";
+    let mut stmt = conn.prepare(query)?;
+    let tasks: Vec<Task> = stmt
+        .query_map(
+            params![include_waiting, database::init::get_date()],
+            |row| {
+                Ok(Task {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    priority: row.get(2)?,
+                    due: row.get(3)?,
+                    entry: row.get(4)?,
+                    project: row.get(5)?,
+                })
+            },
+        )?
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(tasks)
+}
+
+// Pending tasks and recurring templates are deleted once the day given with until has passed
+pub fn expire(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name FROM task
+        WHERE status IN ('pending', 'recurring') AND until < ?",
+    )?;
+    let expired: Vec<(u64, String)> = stmt
+        .query_map(params![database::init::get_date()], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    for (id, name) in expired {
+        set_status(conn, id, "deleted")?;
+        println!("Expired task {}: '{}'", id, name);
+    }
+
     Ok(())
 }
 
-pub fn remove(_conn: &Connection, _id: u64) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+pub fn set_status(conn: &Connection, id: u64, status: &str) -> Result<(), Box<dyn Error>> {
+    if !STATUSES.contains(&status) {
+        return Err(format!(
+            "Invalid status '{}'. Use 'pending', 'done' or 'deleted'.",
+            status
+        )
+        .into());
+    }
+
+    // pending tasks have no end date, done and deleted ones keep track of when they ended
+    let timestamp = database::init::get_timestamp();
+    let end = match status {
+        "pending" => None,
+        _ => Some(timestamp.clone()),
+    };
+
+    conn.execute(
+        "UPDATE task SET status = ?, end = ?, modified = ? WHERE id = ?",
+        params![status, end, timestamp, id],
+    )?;
+
+    // a task that is no longer pending can't be worked on, so its timer is stopped as well
+    if status != "pending" {
+        conn.execute(
+            "UPDATE TimeLog SET end = ? WHERE task_id = ? AND end IS NULL",
+            params![timestamp, id],
+        )?;
+    }
+
     Ok(())
 }
 
-pub fn modify(_conn: &Connection, _tags: HashMap<String, String>) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+pub fn set_project(conn: &Connection, task_id: i64, project: &str) -> Result<(), Box<dyn Error>> {
+    let (project_parents, project_child) = database::init::get_parents(project)?;
+    let project_id = database::project::get_id(conn, project_child, project_parents)?;
+    conn.execute(
+        "INSERT INTO TaskHasProject (task_id, project_id) VALUES (?, ?)",
+        params![task_id, project_id],
+    )?;
+
     Ok(())
 }
+
+fn set_dates(conn: &Connection, id: i64, dates: &[(&str, String)]) -> Result<(), Box<dyn Error>> {
+    for (column, date) in dates {
+        // column is one of scheduled, wait or until, see add
+        let query = format!("UPDATE task SET {} = ? WHERE id = ?", column);
+        conn.execute(&query, params![date, id])?;
+    }
+
+    Ok(())
+}
+
+fn get_name(conn: &Connection, id: u64) -> String {
+    let name: Option<String> = conn
+        .query_row("SELECT name FROM task WHERE id = ?", params![id], |row| {
+            row.get(0)
+        })
+        .optional()
+        .unwrap_or(None);
+
+    match name {
+        Some(name) => name,
+        None => {
+            eprintln!("Task {} does not exist", id);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Accepts daily, weekly, monthly and yearly as well as ISO 8601 durations like P3D or P2W
+pub fn parse_recurrence(value: &str) -> Result<Recurrence, Box<dyn Error>> {
+    let recurrence = match value.to_lowercase().as_str() {
+        "daily" => Some(Recurrence::Days(1)),
+        "weekly" => Some(Recurrence::Days(7)),
+        "monthly" => Some(Recurrence::Months(1)),
+        "yearly" => Some(Recurrence::Months(12)),
+        duration => duration
+            .strip_prefix('p')
+            .filter(|duration| duration.len() > 1)
+            .and_then(|duration| {
+                let (amount, unit) = duration.split_at(duration.len() - 1);
+                let amount: u32 = amount.parse().ok().filter(|amount| *amount > 0)?;
+                match unit {
+                    "d" => Some(Recurrence::Days(amount.into())),
+                    "w" => Some(Recurrence::Days(u64::from(amount) * 7)),
+                    "m" => Some(Recurrence::Months(amount)),
+                    "y" => Some(Recurrence::Months(amount * 12)),
+                    _ => None,
+                }
+            }),
+    };
+
+    recurrence.ok_or_else(|| {
+        format!(
+            "Invalid recurrence '{}'. Use 'daily', 'weekly', 'monthly', 'yearly' or a duration like 'P3D'.",
+            value
+        )
+        .into()
+    })
+}
+
+fn parse_priority(value: &str) -> Result<String, Box<dyn Error>> {
+    let priority = value.to_uppercase();
+    if !PRIORITIES.contains(&priority.as_str()) {
+        return Err(format!("Invalid priority '{}'. Use 'H', 'M' or 'L'.", value).into());
+    }
+
+    Ok(priority)
+}
+
+fn none_if_empty(value: &str) -> Option<&str> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_recurrence() {
+        assert_eq!(parse_recurrence("weekly").unwrap(), Recurrence::Days(7));
+        assert_eq!(parse_recurrence("Monthly").unwrap(), Recurrence::Months(1));
+        assert_eq!(parse_recurrence("P3D").unwrap(), Recurrence::Days(3));
+        assert_eq!(parse_recurrence("P2W").unwrap(), Recurrence::Days(14));
+        assert_eq!(parse_recurrence("P1Y").unwrap(), Recurrence::Months(12));
+        assert!(parse_recurrence("P0D").is_err());
+        assert!(parse_recurrence("P3").is_err());
+        assert!(parse_recurrence("fortnightly").is_err());
+    }
+
+    #[test]
+    fn test_recurrence_nth() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 31).unwrap();
+
+        assert_eq!(
+            Recurrence::Days(7).nth(date, 2),
+            NaiveDate::from_ymd_opt(2026, 2, 14).unwrap()
+        );
+        assert_eq!(
+            Recurrence::Months(1).nth(date, 1),
+            NaiveDate::from_ymd_opt(2026, 2, 28).unwrap()
+        );
+        assert_eq!(
+            Recurrence::Months(1).nth(date, 2),
+            NaiveDate::from_ymd_opt(2026, 3, 31).unwrap()
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use crate::database;
+use chrono::{DateTime, Local, NaiveDateTime};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_derive::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+
+#[derive(Debug, Deserialize)]
+pub struct TaskwarriorTask {
+    pub uuid: String,
+    pub description: String,
+    pub status: String,
+    pub project: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub due: Option<String>,
+    pub scheduled: Option<String>,
+    pub wait: Option<String>,
+    pub until: Option<String>,
+    pub priority: Option<String>,
+    pub entry: Option<String>,
+    pub modified: Option<String>,
+    pub end: Option<String>,
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+    // older versions export a comma separated string, newer ones a list
+    pub depends: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Annotation {
+    pub entry: Option<String>,
+    pub description: String,
+}
+
+// Accepts both a JSON array and one JSON object per line, as written by older taskwarrior versions
+pub fn parse(content: &str) -> Result<Vec<TaskwarriorTask>, Box<dyn Error>> {
+    if content.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(content)?);
+    }
+
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line.trim().trim_end_matches(','))?))
+        .collect()
+}
+
+// Tasks are matched by their taskwarrior uuid, so importing the same export twice updates the
+// existing tasks instead of adding them again
+pub fn import(conn: &Connection, tasks: &[TaskwarriorTask]) -> Result<(), Box<dyn Error>> {
+    let mut ids: HashMap<&str, i64> = HashMap::new();
+    let mut added = 0;
+    let mut updated = 0;
+
+    for task in tasks {
+        let status = match task.status.as_str() {
+            "pending" | "waiting" => "pending",
+            "completed" => "done",
+            "deleted" => "deleted",
+            _ => {
+                println!(
+                    "Skipping {} task '{}', only its instances are imported",
+                    task.status, task.description
+                );
+                continue;
+            }
+        };
+
+        let now = database::init::get_timestamp();
+        let entry = task
+            .entry
+            .as_deref()
+            .and_then(parse_timestamp)
+            .unwrap_or(now.clone());
+        let modified = task
+            .modified
+            .as_deref()
+            .and_then(parse_timestamp)
+            .unwrap_or(now);
+        let end = task.end.as_deref().and_then(parse_timestamp);
+        let due = task.due.as_deref().and_then(parse_date);
+        let scheduled = task.scheduled.as_deref().and_then(parse_date);
+        let wait = task.wait.as_deref().and_then(parse_date);
+        let until = task.until.as_deref().and_then(parse_date);
+        let priority = task
+            .priority
+            .as_deref()
+            .filter(|priority| ["H", "M", "L"].contains(priority));
+
+        let existing_id: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM task WHERE uuid = ?",
+                params![task.uuid],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let id = match existing_id {
+            Some(id) => {
+                conn.execute(
+                    "UPDATE task SET name = ?, status = ?, priority = ?, due = ?, scheduled = ?,
+                        wait = ?, until = ?, entry = ?, modified = ?, end = ?
+                    WHERE id = ?",
+                    params![
+                        task.description,
+                        status,
+                        priority,
+                        due,
+                        scheduled,
+                        wait,
+                        until,
+                        entry,
+                        modified,
+                        end,
+                        id
+                    ],
+                )?;
+                updated += 1;
+                id
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO task (name, status, priority, due, scheduled, wait, until, entry,
+                        modified, end, uuid)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    params![
+                        task.description,
+                        status,
+                        priority,
+                        due,
+                        scheduled,
+                        wait,
+                        until,
+                        entry,
+                        modified,
+                        end,
+                        task.uuid
+                    ],
+                )?;
+                added += 1;
+                conn.last_insert_rowid()
+            }
+        };
+        ids.insert(&task.uuid, id);
+
+        // taskwarrior separates sub-projects with dots
+        conn.execute("DELETE FROM TaskHasProject WHERE task_id = ?", params![id])?;
+        if let Some(project) = &task.project {
+            database::task::set_project(conn, id, &project.replace('.', "/"))?;
+        }
+
+        conn.execute("DELETE FROM TaskHasContext WHERE task_id = ?", params![id])?;
+        for tag in &task.tags {
+            let context_id = database::context::get_id(conn, tag, Vec::new())?;
+            conn.execute(
+                "INSERT INTO TaskHasContext (task_id, context_id) VALUES (?, ?)",
+                params![id, context_id],
+            )?;
+        }
+
+        conn.execute("DELETE FROM TaskAnnotation WHERE task_id = ?", params![id])?;
+        for annotation in &task.annotations {
+            conn.execute(
+                "INSERT INTO TaskAnnotation (task_id, entry, description) VALUES (?, ?, ?)",
+                params![
+                    id,
+                    annotation.entry.as_deref().and_then(parse_timestamp),
+                    annotation.description
+                ],
+            )?;
+        }
+    }
+
+    // dependencies can point at tasks later in the file, so they are linked once all tasks exist
+    for task in tasks {
+        let Some(id) = ids.get(task.uuid.as_str()) else {
+            continue;
+        };
+
+        let mut depends = Vec::new();
+        for uuid in get_depends(task) {
+            let depends_on_id: Option<i64> = conn
+                .query_row("SELECT id FROM task WHERE uuid = ?", params![uuid], |row| {
+                    row.get(0)
+                })
+                .optional()?;
+            match depends_on_id {
+                Some(depends_on_id) => depends.push(depends_on_id),
+                None => println!(
+                    "Skipping unknown dependency {} of task '{}'",
+                    uuid, task.description
+                ),
+            }
+        }
+
+        database::dependency::set(conn, *id, &depends)?;
+    }
+
+    println!("Imported {} new and {} updated tasks", added, updated);
+    Ok(())
+}
+
+fn get_depends(task: &TaskwarriorTask) -> Vec<String> {
+    match &task.depends {
+        Some(Value::String(depends)) => depends
+            .split(',')
+            .filter(|uuid| !uuid.is_empty())
+            .map(String::from)
+            .collect(),
+        Some(Value::Array(depends)) => depends
+            .iter()
+            .filter_map(|uuid| uuid.as_str().map(String::from))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Taskwarrior stores UTC timestamps like 20261101T230000Z, cerebra uses local time
+fn parse_timestamp(value: &str) -> Option<String> {
+    let timestamp = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ").ok()?;
+    let timestamp: DateTime<Local> = timestamp.and_utc().with_timezone(&Local);
+
+    Some(timestamp.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+// Task dates are kept without a time of day
+fn parse_date(value: &str) -> Option<String> {
+    parse_timestamp(value).map(|timestamp| timestamp[..10].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_export() {
+        let content = r#"[
+            {"uuid": "a", "description": "first", "status": "pending", "tags": ["home"],
+                "depends": "b,c"},
+            {"uuid": "b", "description": "second", "status": "completed",
+                "depends": ["c"], "annotations": [{"entry": "20260101T120000Z", "description": "note"}]}
+        ]"#;
+        let tasks = parse(content).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].tags, vec!["home"]);
+        assert_eq!(get_depends(&tasks[0]), vec!["b", "c"]);
+        assert_eq!(get_depends(&tasks[1]), vec!["c"]);
+        assert_eq!(tasks[1].annotations[0].description, "note");
+
+        let lines = r#"{"uuid": "a", "description": "first", "status": "pending"}
+{"uuid": "b", "description": "second", "status": "deleted"}"#;
+        assert_eq!(parse(lines).unwrap().len(), 2);
+    }
+}
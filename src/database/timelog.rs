@@ -0,0 +1,98 @@
+use crate::database;
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::error::Error;
+
+pub struct TimeEntry {
+    pub start: NaiveDateTime,
+    pub end: Option<NaiveDateTime>,
+    pub project_id: Option<i64>,
+}
+
+// Only one task can be tracked at a time, so starting a task stops the running one
+pub fn start(conn: &Connection, task_id: u64) -> Result<(), Box<dyn Error>> {
+    let task: Option<(String, String)> = conn
+        .query_row(
+            "SELECT name, status FROM task WHERE id = ?",
+            params![task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    let name = match task {
+        Some((name, status)) if status == "pending" => name,
+        Some((_, status)) => {
+            eprintln!(
+                "Task {} is {}, only pending tasks can be started",
+                task_id, status
+            );
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("Task {} does not exist", task_id);
+            std::process::exit(1);
+        }
+    };
+
+    stop(conn)?;
+
+    conn.execute(
+        "INSERT INTO TimeLog (task_id, start) VALUES (?, ?)",
+        params![task_id, database::init::get_timestamp()],
+    )?;
+    println!("Started task {}: '{}'", task_id, name);
+
+    Ok(())
+}
+
+// Returns whether a task was running
+pub fn stop(conn: &Connection) -> Result<bool, Box<dyn Error>> {
+    let active: Option<(i64, i64, String)> = conn
+        .query_row(
+            "SELECT TimeLog.id, TimeLog.task_id, task.name FROM TimeLog
+            JOIN task ON task.id = TimeLog.task_id
+            WHERE TimeLog.end IS NULL",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+
+    match active {
+        Some((id, task_id, name)) => {
+            conn.execute(
+                "UPDATE TimeLog SET end = ? WHERE id = ?",
+                params![database::init::get_timestamp(), id],
+            )?;
+            println!("Stopped task {}: '{}'", task_id, name);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+// Time is booked on the project of the task, entries that are still running have no end
+pub fn get_entries(conn: &Connection) -> Result<Vec<TimeEntry>, Box<dyn Error>> {
+    let query = "SELECT TimeLog.start, TimeLog.end, TaskHasProject.project_id
+        FROM TimeLog
+        LEFT JOIN TaskHasProject ON TaskHasProject.task_id = TimeLog.task_id
+        ORDER BY TimeLog.start";
+    let mut stmt = conn.prepare(query)?;
+    let entries: Vec<TimeEntry> = stmt
+        .query_map([], |row| {
+            let start: String = row.get(0)?;
+            let end: Option<String> = row.get(1)?;
+            Ok((start, end, row.get(2)?))
+        })?
+        .filter_map(Result::ok)
+        .filter_map(|(start, end, project_id)| {
+            Some(TimeEntry {
+                start: NaiveDateTime::parse_from_str(&start, "%Y-%m-%d %H:%M:%S").ok()?,
+                end: end
+                    .and_then(|end| NaiveDateTime::parse_from_str(&end, "%Y-%m-%d %H:%M:%S").ok()),
+                project_id,
+            })
+        })
+        .collect();
+
+    Ok(entries)
+}
@@ -0,0 +1,325 @@
+use crate::database;
+use chrono::{DateTime, Local};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+const ANCHOR_PREFIX: &str = "<!-- cerebra:task:";
+const ANCHOR_SUFFIX: &str = " -->";
+
+#[derive(Debug, PartialEq)]
+struct TodoLine {
+    id: Option<i64>,
+    done: bool,
+    text: String,
+}
+
+// Reads every todo file back into the database. A file only wins over the database for tasks that
+// were not modified after the file was last written, so edits on both sides don't overwrite each
+// other.
+pub fn import(conn: &Connection, todo_path: &Path) -> Result<(), Box<dyn Error>> {
+    for file in database::init::get_markdown_files(todo_path)? {
+        let project = get_project_path(todo_path, &file);
+        let file_modified: DateTime<Local> = fs::metadata(&file)?.modified()?.into();
+        let file_modified = file_modified.format("%Y-%m-%d %H:%M:%S").to_string();
+
+        let content = fs::read_to_string(&file)?;
+        let lines: Vec<TodoLine> = content.lines().filter_map(parse_line).collect();
+
+        for (position, line) in lines.iter().enumerate() {
+            match line.id {
+                Some(id) => import_task(conn, id, line, position as i64, &file_modified)?,
+                None => {
+                    let id = database::task::add_to_db(conn, &line.text, &project, "", "")?;
+                    conn.execute(
+                        "UPDATE task SET position = ? WHERE id = ?",
+                        params![position as i64, id],
+                    )?;
+                    if line.done {
+                        database::task::set_status(conn, id as u64, "done")?;
+                    }
+                    println!("Added task {}: '{}' from {}", id, line.text, file.display());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn import_task(
+    conn: &Connection,
+    id: i64,
+    line: &TodoLine,
+    position: i64,
+    file_modified: &str,
+) -> Result<(), Box<dyn Error>> {
+    let task: Option<(String, String, String)> = conn
+        .query_row(
+            "SELECT name, status, modified FROM task WHERE id = ?",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+
+    // deleted tasks and ids that were made up in the editor are left alone
+    let (name, status, modified) = match task {
+        Some(task) if task.1 == "pending" || task.1 == "done" => task,
+        _ => return Ok(()),
+    };
+    if modified.as_str() > file_modified {
+        return Ok(());
+    }
+
+    conn.execute(
+        "UPDATE task SET position = ? WHERE id = ?",
+        params![position, id],
+    )?;
+
+    if name != line.text {
+        conn.execute(
+            "UPDATE task SET name = ?, modified = ? WHERE id = ?",
+            params![line.text, database::init::get_timestamp(), id],
+        )?;
+        println!("Renamed task {} to '{}'", id, line.text);
+    }
+
+    let file_status = if line.done { "done" } else { "pending" };
+    if status != file_status {
+        database::task::set_status(conn, id as u64, file_status)?;
+        println!("Marked task {} as {}", id, file_status);
+    }
+
+    Ok(())
+}
+
+// Writes one file per project with its pending and done tasks. Checklist lines of an existing file
+// are updated in place, so headings and notes around them are kept
+pub fn render(conn: &Connection, todo_path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut stmt = conn.prepare("SELECT id FROM project ORDER BY id")?;
+    let project_ids: Vec<i64> = stmt
+        .query_map([], |row| row.get(0))?
+        .filter_map(Result::ok)
+        .collect();
+
+    for project_id in project_ids {
+        let project = database::project::get_path(conn, project_id)?;
+        let file = todo_path.join(format!("{}.md", project));
+
+        let mut stmt = conn.prepare(
+            "SELECT task.id, task.status, task.name FROM task
+            JOIN TaskHasProject ON TaskHasProject.task_id = task.id
+            WHERE TaskHasProject.project_id = ? AND task.status IN ('pending', 'done')
+            ORDER BY task.position IS NULL, task.position, task.id",
+        )?;
+        let tasks: Vec<TodoLine> = stmt
+            .query_map(params![project_id], |row| {
+                let status: String = row.get(1)?;
+                Ok(TodoLine {
+                    id: Some(row.get(0)?),
+                    done: status == "done",
+                    text: row.get(2)?,
+                })
+            })?
+            .filter_map(Result::ok)
+            .collect();
+
+        let content = if file.exists() {
+            update_lines(&fs::read_to_string(&file)?, &tasks)
+        } else if tasks.is_empty() {
+            continue;
+        } else {
+            let lines: Vec<String> = tasks.iter().map(format_line).collect();
+            format!("# {}\n\n{}\n", project, lines.join("\n"))
+        };
+
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if !file.exists() || fs::read_to_string(&file)? != content {
+            fs::write(&file, content)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Rewrites the checklist lines of a file from the tasks. Lines of tasks that are gone are dropped,
+// lines without an id take the id of the task they were imported as and tasks that are not in the
+// file yet are added after the last checklist line
+fn update_lines(content: &str, tasks: &[TodoLine]) -> String {
+    let mut written = vec![false; tasks.len()];
+    let mut lines: Vec<String> = Vec::new();
+    let mut last_checkbox = None;
+
+    for line in content.lines() {
+        let Some(parsed) = parse_line(line) else {
+            lines.push(line.to_string());
+            continue;
+        };
+
+        let index = tasks.iter().enumerate().position(|(index, task)| {
+            !written[index]
+                && match parsed.id {
+                    Some(id) => task.id == Some(id),
+                    None => task.text == parsed.text,
+                }
+        });
+        match (index, parsed.id) {
+            (Some(index), _) => {
+                written[index] = true;
+                let indent = &line[..line.len() - line.trim_start().len()];
+                lines.push(format!("{}{}", indent, format_line(&tasks[index])));
+            }
+            // not imported yet, so it stays as it is
+            (None, None) => lines.push(line.to_string()),
+            (None, Some(_)) => continue,
+        }
+        last_checkbox = Some(lines.len());
+    }
+
+    let missing: Vec<String> = tasks
+        .iter()
+        .zip(&written)
+        .filter(|(_, written)| !**written)
+        .map(|(task, _)| format_line(task))
+        .collect();
+    let at = last_checkbox.unwrap_or(lines.len());
+    lines.splice(at..at, missing);
+
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    updated
+}
+
+// todo_path/work/client.md belongs to the project work/client
+fn get_project_path(todo_path: &Path, file: &Path) -> String {
+    file.strip_prefix(todo_path)
+        .unwrap_or(file)
+        .with_extension("")
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+fn parse_line(line: &str) -> Option<TodoLine> {
+    let line = line.trim();
+    let (done, rest) = if let Some(rest) = line.strip_prefix("- [ ] ") {
+        (false, rest)
+    } else if let Some(rest) = line
+        .strip_prefix("- [x] ")
+        .or_else(|| line.strip_prefix("- [X] "))
+    {
+        (true, rest)
+    } else {
+        return None;
+    };
+
+    let (text, id) = match rest.find(ANCHOR_PREFIX) {
+        Some(start) => {
+            let anchor = &rest[start + ANCHOR_PREFIX.len()..];
+            let id = anchor
+                .strip_suffix(ANCHOR_SUFFIX)
+                .and_then(|id| id.trim().parse().ok());
+            (&rest[..start], id)
+        }
+        None => (rest, None),
+    };
+
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    Some(TodoLine {
+        id,
+        done,
+        text: text.to_string(),
+    })
+}
+
+fn format_line(line: &TodoLine) -> String {
+    let checkbox = if line.done { "[x]" } else { "[ ]" };
+    match line.id {
+        Some(id) => format!(
+            "- {} {} {}{}{}",
+            checkbox, line.text, ANCHOR_PREFIX, id, ANCHOR_SUFFIX
+        ),
+        None => format!("- {} {}", checkbox, line.text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line("- [x] write report <!-- cerebra:task:12 -->"),
+            Some(TodoLine {
+                id: Some(12),
+                done: true,
+                text: "write report".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_line("  - [ ] new task"),
+            Some(TodoLine {
+                id: None,
+                done: false,
+                text: "new task".to_string(),
+            })
+        );
+        assert_eq!(parse_line("# work"), None);
+        assert_eq!(parse_line("- [ ] "), None);
+    }
+
+    #[test]
+    fn test_update_lines() {
+        let content = "# work\n\nSome notes.\n\n\
+            - [ ] call the bank <!-- cerebra:task:1 -->\n\
+            - [ ] deleted <!-- cerebra:task:2 -->\n\
+            - [ ] new task\n\n\
+            ## Later\n";
+        let tasks = [
+            TodoLine {
+                id: Some(1),
+                done: true,
+                text: "call the bank".to_string(),
+            },
+            TodoLine {
+                id: Some(3),
+                done: false,
+                text: "new task".to_string(),
+            },
+            TodoLine {
+                id: Some(4),
+                done: false,
+                text: "added elsewhere".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            update_lines(content, &tasks),
+            "# work\n\nSome notes.\n\n\
+            - [x] call the bank <!-- cerebra:task:1 -->\n\
+            - [ ] new task <!-- cerebra:task:3 -->\n\
+            - [ ] added elsewhere <!-- cerebra:task:4 -->\n\n\
+            ## Later\n"
+        );
+    }
+
+    #[test]
+    fn test_format_line_round_trip() {
+        let line = TodoLine {
+            id: Some(3),
+            done: false,
+            text: "call the bank".to_string(),
+        };
+
+        assert_eq!(parse_line(&format_line(&line)), Some(line));
+    }
+}
@@ -29,3 +29,35 @@ pub fn get_id(
         }
     }
 }
+
+// Full name of a topic including its parents, e.g. science/physics. Root topics point to the
+// parent SQLITE_NULL
+pub fn get_path(conn: &Connection, id: i64) -> Result<String, Box<dyn Error>> {
+    crate::database::init::get_path(
+        conn,
+        "topic",
+        "parent_topic_id",
+        Some(SQLITE_NULL as i64),
+        id,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_path() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE Topic (id INTEGER PRIMARY KEY, name TEXT, parent_topic_id INTEGER);
+            INSERT INTO Topic VALUES (1, 'science', 5), (2, 'physics', 1), (5, 'math', 5);
+            INSERT INTO Topic VALUES (3, 'a', 4), (4, 'b', 3);",
+        )
+        .unwrap();
+
+        assert_eq!(get_path(&conn, 2).unwrap(), "science/physics");
+        assert_eq!(get_path(&conn, 5).unwrap(), "math");
+        assert_eq!(get_path(&conn, 3).unwrap(), "b/a");
+    }
+}
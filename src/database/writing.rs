@@ -1,22 +1,554 @@
-use rusqlite::Connection;
+use crate::database;
+use chrono::{Days, Local};
+use rusqlite::{params, Connection, OptionalExtension};
 use std::collections::HashMap;
 use std::error::Error;
+use std::fs;
+use std::path::Path;
 
+pub struct AttachedNote {
+    pub id: i64,
+    pub content: String,
+    pub topic: String,
+    pub sources: Vec<String>,
+}
+
+// Word counts per writing are recorded once a day, the words written on a day are the difference
+// to the previous recorded count. The count of the day before the writing was added is its
+// baseline, so the words already in an existing file don't count as written.
 pub fn add(
-    _conn: &Connection,
-    _content: &str,
-    _tags: HashMap<String, String>,
+    writing_directory: &Path,
+    conn: &Connection,
+    content: &str,
+    tags: HashMap<String, String>,
 ) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+    let slug = database::init::slugify(content);
+    let mut path = writing_directory.join(format!("{}.md", slug));
+    let mut goal: Option<i64> = None;
+    let mut daily_goal: Option<i64> = None;
+    let mut project = String::new();
+    let mut topic = String::new();
+
+    for (key, value) in &tags {
+        match key.as_str() {
+            "path" => path = writing_directory.join(value),
+            "goal" => goal = parse_goal(value)?,
+            "daily" => daily_goal = parse_goal(value)?,
+            "project" => project = value.to_string(),
+            "topic" => topic = value.to_string(),
+            _ => {
+                eprintln!(
+                    "Invalid tag '{}'. Use 'path', 'goal', 'daily', 'project' or 'topic'.",
+                    key
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if slug.is_empty() && !tags.contains_key("path") {
+        eprintln!(
+            "Cannot make a file name from '{}'. Give one with path:<file>.",
+            content
+        );
+        std::process::exit(1);
+    }
+
+    // names like Foo and foo end up in the same file
+    let existing_id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM writing WHERE path = ?",
+            params![path.to_string_lossy()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(existing_id) = existing_id {
+        eprintln!(
+            "Writing {} already uses {}. Give another file with path:<file>.",
+            existing_id,
+            path.display()
+        );
+        std::process::exit(1);
+    }
+
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, format!("# {}\n", content))?;
+    }
+    let words = count_words(&fs::read_to_string(&path)?) as i64;
+
+    conn.execute(
+        "INSERT INTO writing (name, kind, path, date, words, modified, goal, daily_goal)
+        VALUES (?, 'writing', ?, ?, ?, ?, ?, ?)",
+        params![
+            content,
+            path.to_string_lossy(),
+            database::init::get_date(),
+            words,
+            database::init::get_timestamp(),
+            goal,
+            daily_goal
+        ],
+    )?;
+    let id = conn.last_insert_rowid();
+    let yesterday = Local::now().date_naive() - Days::new(1);
+    conn.execute(
+        "INSERT INTO WritingSnapshot (writing_id, date, words) VALUES (?, ?, ?)",
+        params![id, yesterday.format("%Y-%m-%d").to_string(), words],
+    )?;
+    snapshot(conn, id, words)?;
+
+    if !project.is_empty() {
+        set_project(conn, id, &project)?;
+    }
+    if !topic.is_empty() {
+        set_topics(conn, id, &[topic.clone()])?;
+    }
+
+    println!(
+        "Added writing {}: '{}' at {} with goal '{}', daily goal '{}', project '{}', topic '{}'",
+        id,
+        content,
+        path.display(),
+        goal.map(|goal| goal.to_string()).unwrap_or_default(),
+        daily_goal.map(|goal| goal.to_string()).unwrap_or_default(),
+        project,
+        topic
+    );
+
     Ok(())
 }
 
-pub fn remove(_conn: &Connection, _id: u64) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+// The file itself is kept, only the database entry is removed
+pub fn remove(conn: &Connection, id: u64) -> Result<(), Box<dyn Error>> {
+    let name = get_name(conn, id);
+
+    let mut input = String::new();
+    println!(
+        "Are you sure you want to delete writing '{}'? The file is kept. (y/n)",
+        name
+    );
+    std::io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+
+    if input.trim() != "y" {
+        println!("Aborting deletion");
+        return Ok(());
+    }
+
+    for table in [
+        "WritingHasProject",
+        "WritingHasNote",
+        "WritingHasTopic",
+        "WritingHasContext",
+        "WritingSnapshot",
+    ] {
+        conn.execute(
+            &format!("DELETE FROM {} WHERE writing_id = ?", table),
+            params![id],
+        )?;
+    }
+    conn.execute("DELETE FROM writing WHERE id = ?", params![id])?;
+
+    println!("Deleted writing {}", id);
     Ok(())
 }
 
-pub fn modify(_conn: &Connection, _tags: HashMap<String, String>) -> Result<(), Box<dyn Error>> {
-    println!("Coming soon!");
+pub fn modify(
+    conn: &Connection,
+    id: u64,
+    tags: HashMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    get_name(conn, id);
+
+    if tags.is_empty() {
+        eprintln!("No tags provided for modification");
+        std::process::exit(1);
+    }
+
+    for (key, value) in &tags {
+        match key.as_str() {
+            "name" => {
+                conn.execute(
+                    "UPDATE writing SET name = ? WHERE id = ?",
+                    params![value, id],
+                )?;
+            }
+            "goal" | "daily" => {
+                let column = if key == "goal" { "goal" } else { "daily_goal" };
+                conn.execute(
+                    &format!("UPDATE writing SET {} = ? WHERE id = ?", column),
+                    params![parse_goal(value)?, id],
+                )?;
+            }
+            "project" => {
+                conn.execute(
+                    "DELETE FROM WritingHasProject WHERE writing_id = ?",
+                    params![id],
+                )?;
+                conn.execute(
+                    "UPDATE writing SET project_id = NULL WHERE id = ?",
+                    params![id],
+                )?;
+                if !value.is_empty() {
+                    set_project(conn, id as i64, value)?;
+                }
+            }
+            "topic" => {
+                conn.execute(
+                    "DELETE FROM WritingHasTopic WHERE writing_id = ?",
+                    params![id],
+                )?;
+                if !value.is_empty() {
+                    set_topics(conn, id as i64, &[value.to_string()])?;
+                }
+            }
+            _ => {
+                eprintln!(
+                    "Invalid tag '{}'. Use 'name', 'goal', 'daily', 'project' or 'topic'.",
+                    key
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    println!("Modified writing {}", id);
     Ok(())
 }
+
+// Creates or refreshes the writing row of a journal entry from the file content
+pub fn register_journal(
+    conn: &Connection,
+    path: &Path,
+    date: &str,
+    content: &str,
+) -> Result<i64, Box<dyn Error>> {
+    let path = path.to_string_lossy().to_string();
+    let words = count_words(content) as i64;
+    let modified = database::init::get_timestamp();
+
+    let existing_id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM writing WHERE path = ?",
+            params![path],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let id = match existing_id {
+        Some(id) => {
+            conn.execute(
+                "UPDATE writing SET words = ?, modified = ? WHERE id = ?",
+                params![words, modified, id],
+            )?;
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO writing (name, kind, path, date, words, modified)
+                VALUES (?, 'journal', ?, ?, ?, ?)",
+                params![date, path, date, words, modified],
+            )?;
+            conn.last_insert_rowid()
+        }
+    };
+
+    let (topics, contexts) = get_file_tags(content);
+    conn.execute(
+        "DELETE FROM WritingHasTopic WHERE writing_id = ?",
+        params![id],
+    )?;
+    set_topics(conn, id, &topics)?;
+    conn.execute(
+        "DELETE FROM WritingHasContext WHERE writing_id = ?",
+        params![id],
+    )?;
+    set_contexts(conn, id, &contexts)?;
+    snapshot(conn, id, words)?;
+
+    println!(
+        "Saved journal entry {}: '{}' with {} words, topic '{}', context '{}'",
+        id,
+        date,
+        words,
+        topics.join(","),
+        contexts.join(",")
+    );
+
+    Ok(id)
+}
+
+// Headings, html comments and tags are not part of the text
+pub fn count_words(content: &str) -> usize {
+    strip_comments(content)
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(str::split_whitespace)
+        .filter(|word| !is_tag(word))
+        .count()
+}
+
+// Words like topic:science/physics or context:home anywhere in the text, outside of comments
+pub fn get_file_tags(content: &str) -> (Vec<String>, Vec<String>) {
+    let mut topics = Vec::new();
+    let mut contexts = Vec::new();
+
+    for word in strip_comments(content).split_whitespace() {
+        if let Some(topic) = word.strip_prefix("topic:").filter(|tag| !tag.is_empty()) {
+            if !topics.contains(&topic.to_string()) {
+                topics.push(topic.to_string());
+            }
+        } else if let Some(context) = word.strip_prefix("context:").filter(|tag| !tag.is_empty()) {
+            if !contexts.contains(&context.to_string()) {
+                contexts.push(context.to_string());
+            }
+        }
+    }
+
+    (topics, contexts)
+}
+
+// Appends notes to a writing, in the given order after the notes that are already attached
+// Notes that are already attached keep their place
+pub fn attach(conn: &Connection, id: u64, note_ids: &[i64]) -> Result<(), Box<dyn Error>> {
+    get_name(conn, id);
+
+    for note_id in note_ids {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM note WHERE id = ?)",
+            params![note_id],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Err(format!("Note {} does not exist", note_id).into());
+        }
+    }
+
+    let mut position: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(position), 0) FROM WritingHasNote WHERE writing_id = ?",
+        params![id],
+        |row| row.get(0),
+    )?;
+    let mut attached = 0;
+    for note_id in note_ids {
+        let inserted = conn.execute(
+            "INSERT INTO WritingHasNote (writing_id, note_id, position)
+            SELECT ?1, ?2, ?3 WHERE NOT EXISTS (
+                SELECT 1 FROM WritingHasNote WHERE writing_id = ?1 AND note_id = ?2
+            )",
+            params![id, note_id, position + 1],
+        )?;
+        position += inserted as i64;
+        attached += inserted;
+    }
+
+    println!("Attached {} notes to writing {}", attached, id);
+    Ok(())
+}
+
+// Body and attached notes of a writing, with the topic and sources of each note
+pub fn get_manuscript(
+    conn: &Connection,
+    id: u64,
+) -> Result<(String, String, Vec<AttachedNote>), Box<dyn Error>> {
+    let name = get_name(conn, id);
+    let path: Option<String> = conn.query_row(
+        "SELECT path FROM writing WHERE id = ?",
+        params![id],
+        |row| row.get(0),
+    )?;
+    let body = match path {
+        Some(path) => fs::read_to_string(path)?,
+        None => String::new(),
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT note.id, note.content, COALESCE(topic.name, '')
+        FROM WritingHasNote
+        JOIN note ON note.id = WritingHasNote.note_id
+        LEFT JOIN topic ON topic.id = note.topic_id
+        WHERE WritingHasNote.writing_id = ?
+        ORDER BY WritingHasNote.position, WritingHasNote.rowid",
+    )?;
+    let mut notes: Vec<AttachedNote> = stmt
+        .query_map(params![id], |row| {
+            Ok(AttachedNote {
+                id: row.get(0)?,
+                content: row.get(1)?,
+                topic: row.get(2)?,
+                sources: Vec::new(),
+            })
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    for note in &mut notes {
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT source.name FROM source
+            WHERE source.id = (SELECT source_id FROM note WHERE id = ?1)
+            OR source.id IN (SELECT source_id FROM NoteHasSource WHERE note_id = ?1)
+            ORDER BY source.name",
+        )?;
+        note.sources = stmt
+            .query_map(params![note.id], |row| row.get(0))?
+            .filter_map(Result::ok)
+            .filter(|source: &String| !source.is_empty())
+            .collect();
+    }
+
+    Ok((name, body, notes))
+}
+
+// Counts the words of every writing file again and records them for today
+pub fn refresh(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    let mut stmt = conn.prepare("SELECT id, path FROM writing WHERE path IS NOT NULL")?;
+    let writings: Vec<(i64, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(Result::ok)
+        .collect();
+
+    for (id, path) in writings {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                eprintln!("Skipping writing {}, failed to read {}: {}", id, path, err);
+                continue;
+            }
+        };
+        let words = count_words(&content) as i64;
+
+        conn.execute(
+            "UPDATE writing SET words = ?1, modified = ?2 WHERE id = ?3 AND words != ?1",
+            params![words, database::init::get_timestamp(), id],
+        )?;
+        snapshot(conn, id, words)?;
+    }
+
+    Ok(())
+}
+
+// Records today's word count of a writing, later counts of the same day replace earlier ones
+pub fn snapshot(conn: &Connection, id: i64, words: i64) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "INSERT INTO WritingSnapshot (writing_id, date, words) VALUES (?1, ?2, ?3)
+        ON CONFLICT (writing_id, date) DO UPDATE SET words = ?3",
+        params![id, database::init::get_date(), words],
+    )?;
+
+    Ok(())
+}
+
+fn set_project(conn: &Connection, id: i64, project: &str) -> Result<(), Box<dyn Error>> {
+    let (project_parents, project_child) = database::init::get_parents(project)?;
+    let project_id = database::project::get_id(conn, project_child, project_parents)?;
+    conn.execute(
+        "UPDATE writing SET project_id = ? WHERE id = ?",
+        params![project_id, id],
+    )?;
+
+    Ok(())
+}
+
+fn set_topics(conn: &Connection, id: i64, topics: &[String]) -> Result<(), Box<dyn Error>> {
+    for topic in topics {
+        let (topic_parents, topic_child) = database::init::get_parents(topic)?;
+        let topic_id = database::topic::get_id(conn, topic_child, topic_parents)?;
+        conn.execute(
+            "INSERT INTO WritingHasTopic (writing_id, topic_id) VALUES (?, ?)",
+            params![id, topic_id],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn set_contexts(conn: &Connection, id: i64, contexts: &[String]) -> Result<(), Box<dyn Error>> {
+    for context in contexts {
+        let (context_parents, context_child) = database::init::get_parents(context)?;
+        let context_id = database::context::get_id(conn, context_child, context_parents)?;
+        conn.execute(
+            "INSERT INTO WritingHasContext (writing_id, context_id) VALUES (?, ?)",
+            params![id, context_id],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn get_name(conn: &Connection, id: u64) -> String {
+    let name: Option<String> = conn
+        .query_row(
+            "SELECT name FROM writing WHERE id = ?",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()
+        .unwrap_or(None);
+
+    match name {
+        Some(name) => name,
+        None => {
+            eprintln!("Writing {} does not exist", id);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_goal(value: &str) -> Result<Option<i64>, Box<dyn Error>> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    match value.parse::<i64>() {
+        Ok(goal) if goal > 0 => Ok(Some(goal)),
+        _ => Err(format!("Invalid word goal '{}'. Use a positive number.", value).into()),
+    }
+}
+
+fn is_tag(word: &str) -> bool {
+    word.starts_with("topic:") || word.starts_with("context:")
+}
+
+fn strip_comments(content: &str) -> String {
+    let mut text = String::new();
+    let mut rest = content;
+
+    while let Some(start) = rest.find("<!--") {
+        text.push_str(&rest[..start]);
+        rest = match rest[start..].find("-->") {
+            Some(end) => &rest[start + end + 3..],
+            None => "",
+        };
+    }
+    text.push_str(rest);
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_words() {
+        let content = "# 2026-10-18\n\n<!-- tags go here -->\nWent for a walk. topic:health\n";
+
+        assert_eq!(count_words(content), 4);
+    }
+
+    #[test]
+    fn test_get_file_tags() {
+        let content = "topic:science/physics and context:home\n<!-- topic:example -->\ntopic:health topic:health";
+
+        assert_eq!(
+            get_file_tags(content),
+            (
+                vec!["science/physics".to_string(), "health".to_string()],
+                vec!["home".to_string()]
+            )
+        );
+    }
+}
@@ -137,25 +137,111 @@ enum Commands {
         // The number of entries to display
         #[clap(long, default_value = "30", verbatim_doc_comment)]
         number: u64,
+
+        // Also display waiting and deleted tasks or promoted and discarded ideas
+        #[clap(long, verbatim_doc_comment)]
+        all: bool,
+    },
+    #[command(
+        about = "display the most urgent tasks",
+        long_about = "display pending tasks ranked by their urgency, which is computed from the due date, priority, age, project and dependencies"
+    )]
+    Next {
+        // The number of tasks to display
+        #[clap(long, default_value = "30", verbatim_doc_comment)]
+        number: u64,
+
+        // Also display tasks that are waiting
+        #[clap(long, verbatim_doc_comment)]
+        all: bool,
+    },
+    #[command(
+        about = "display the dependencies of a task",
+        long_about = "display a tree of the tasks a task depends on and the tasks it blocks"
+    )]
+    Deps {
+        // The ID of the task
+        #[clap(index = 1, required = true, value_name = "ID", verbatim_doc_comment)]
+        id: u64,
+    },
+    #[command(
+        about = "manage projects",
+        long_about = None
+    )]
+    Project {
+        #[command(subcommand)]
+        command: ProjectCommands,
+    },
+    #[command(
+        about = "write today's journal entry",
+        long_about = "open today's journal entry in the editor, creating it on first use, and record its word count and tags"
+    )]
+    Journal,
+    #[command(about = "manage code snippets", long_about = None)]
+    Code {
+        #[command(subcommand)]
+        command: CodeCommands,
+    },
+    #[command(about = "manage writings", long_about = None)]
+    Writing {
+        #[command(subcommand)]
+        command: WritingCommands,
+    },
+    #[command(about = "manage sources", long_about = None)]
+    Source {
+        #[command(subcommand)]
+        command: SourceCommands,
+    },
+    #[command(about = "manage ideas", long_about = None)]
+    Idea {
+        #[command(subcommand)]
+        command: IdeaCommands,
+    },
+    #[command(
+        about = "start tracking time on a task",
+        long_about = "start tracking time on a task, stopping the task that is currently running"
+    )]
+    Start {
+        // The ID of the task
+        #[clap(index = 1, required = true, value_name = "ID", verbatim_doc_comment)]
+        id: u64,
+    },
+    #[command(about = "stop tracking time", long_about = None)]
+    Stop,
+    #[command(
+        about = "display the tracked hours",
+        long_about = "display the tracked hours per project and topic"
+    )]
+    Timesheet {
+        // Only count the hours of the current week
+        #[clap(long, verbatim_doc_comment)]
+        week: bool,
+    },
+    #[command(about = "display reports", long_about = None)]
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
     },
-    #[command(about = "add an entry", long_about = None)]
+    #[command(
+        about = "add an entry",
+        long_about = "add an entry, e.g. `cerebra add note \"This is a note\" topic:science/physics`. `cerebra add note topic:science/physics` or `-e` opens the editor to write a longer note, with the tags in a header at the top."
+    )]
     Add {
         // The type of the entry
         #[clap(index = 1, required = true, value_name = "TYPE", verbatim_doc_comment)]
         entry_type: String,
 
-        // The content of the entry
-        #[clap(
-            index = 2,
-            required = true,
-            value_name = "CONTENT",
-            verbatim_doc_comment
-        )]
-        content: String,
+        // The content of the entry, notes without one are written in the editor
+        #[clap(index = 2, value_name = "CONTENT", verbatim_doc_comment)]
+        content: Option<String>,
 
         // The tags of the entry, e.g. source:book or topic:it/programming/rust
         #[clap(index = 3, num_args(1..), value_name = "TAGS", verbatim_doc_comment)]
         tags: Vec<String>,
+
+        // Write the note in the editor, starting from the given content and tags
+        #[clap(short, long, verbatim_doc_comment)]
+        edit: bool,
     },
     #[command(
         about = "remove an entry",
@@ -187,6 +273,19 @@ enum Commands {
         #[clap(index = 3, num_args(1..), value_name = "TAGS", verbatim_doc_comment)]
         tags: Vec<String>,
     },
+    #[command(
+        about = "edit an entry in the editor",
+        long_about = "open the file of a note, writing or code snippet in the editor, notes at their first line. Changes to a note are written back into the database."
+    )]
+    Edit {
+        // The type of the entry, either note, writings or code
+        #[clap(index = 1, required = true, value_name = "TYPE", verbatim_doc_comment)]
+        entry_type: String,
+
+        // The ID of the entry to edit
+        #[clap(index = 2, required = true, value_name = "ID", verbatim_doc_comment)]
+        id: u64,
+    },
     #[command(about = "search for an entry", long_about = None)]
     Search {
         // The type of entries to search for
@@ -206,6 +305,60 @@ enum Commands {
         #[clap(long, default_value = "30", verbatim_doc_comment)]
         number: u64,
     },
+    #[command(
+        about = "sync the database with files",
+        long_about = "sync the database with files, e.g. `todos` writes every project's tasks to a Markdown checklist in the todo directory and reads ticked boxes, edits and reordering back"
+    )]
+    Sync {
+        // What to sync, currently only todos
+        #[clap(index = 1, required = true, value_name = "TYPE", verbatim_doc_comment)]
+        sync_type: String,
+    },
+    #[command(
+        about = "import entries from a file",
+        long_about = "import entries from a file, e.g. `taskwarrior` reads the JSON written by `task export` and `bibtex` reads the sources of a .bib file"
+    )]
+    Import {
+        // The format to import, either taskwarrior or bibtex
+        #[clap(index = 1, required = true, value_name = "TYPE", verbatim_doc_comment)]
+        import_type: String,
+
+        // The file to read from
+        #[clap(index = 2, required = true, value_name = "PATH", verbatim_doc_comment)]
+        file: PathBuf,
+    },
+    #[command(
+        about = "attach a file to a source",
+        long_about = "copy a file, e.g. a PDF, image or video, into the misc directory and attach it to a source, e.g. `cerebra attach book.pdf source:\"Some Book\" note:17`"
+    )]
+    Attach {
+        // The file to attach
+        #[clap(index = 1, required = true, value_name = "PATH", verbatim_doc_comment)]
+        file: PathBuf,
+
+        // The tags of the attachment, e.g. source:book or note:17
+        #[clap(index = 2, num_args(1..), value_name = "TAGS", verbatim_doc_comment)]
+        tags: Vec<String>,
+    },
+    #[command(
+        about = "export entries to a file",
+        long_about = "export entries to a file, e.g. `ics` writes tasks with a due or scheduled date and project deadlines to an iCalendar file, `bibtex` writes all sources to a .bib file and `notes` copies the note files into a directory with a bibliography of their citations"
+    )]
+    Export {
+        // The format to export, either ics, bibtex or notes
+        #[clap(index = 1, required = true, value_name = "TYPE", verbatim_doc_comment)]
+        export_type: String,
+
+        // The file to write to, cerebra.ics or cerebra.bib by default, or the directory for notes,
+        // cerebra-notes by default
+        #[clap(short, long, value_name = "PATH", verbatim_doc_comment)]
+        output: Option<PathBuf>,
+    },
+    #[command(
+        about = "check the citations in notes",
+        long_about = "link the [@key] citations in notes to the sources with that citation key and list the keys that don't resolve"
+    )]
+    Check,
     #[command(
         about = "draw a graph",
         long_about = "draw a graph of either the database or relationships"
@@ -218,11 +371,172 @@ enum Commands {
     Intro,
 }
 
+#[derive(Subcommand)]
+enum ProjectCommands {
+    #[command(
+        about = "show a project",
+        long_about = "show a project with the progress of its tasks and its attached notes, ideas, writings and code"
+    )]
+    Show {
+        // The name of the project, e.g. work or work/client
+        #[clap(index = 1, required = true, value_name = "NAME", verbatim_doc_comment)]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum CodeCommands {
+    #[command(
+        about = "print a code snippet",
+        long_about = "print a code snippet exactly as stored, e.g. to pipe it into another program"
+    )]
+    Cat {
+        // The ID of the code snippet
+        #[clap(index = 1, required = true, value_name = "ID", verbatim_doc_comment)]
+        id: u64,
+    },
+    #[command(
+        about = "extract code blocks from notes",
+        long_about = "turn the fenced code blocks in the note files into code snippets linked to their notes, blocks that were extracted before are skipped"
+    )]
+    Extract,
+    #[command(
+        about = "check code snippets against their files",
+        long_about = "check whether the code snippets taken from a file still match that file, update the line range of snippets that moved and offer to refresh the ones that changed"
+    )]
+    Check,
+}
+
+#[derive(Subcommand)]
+enum WritingCommands {
+    #[command(
+        about = "display writing statistics",
+        long_about = "display the words written per day, streaks and the progress towards the word goals of each writing"
+    )]
+    Stats,
+    #[command(
+        about = "attach notes to a writing",
+        long_about = "attach notes to a writing, e.g. `cerebra writing attach 3 note:17 note:42`. The notes are added after the ones that are already attached, in the given order."
+    )]
+    Attach {
+        // The ID of the writing
+        #[clap(index = 1, required = true, value_name = "ID", verbatim_doc_comment)]
+        id: u64,
+
+        // The notes to attach, e.g. note:17
+        #[clap(
+            index = 2,
+            required = true,
+            value_name = "ENTRIES",
+            verbatim_doc_comment
+        )]
+        entries: Vec<String>,
+    },
+    #[command(
+        about = "compile a writing into a manuscript",
+        long_about = "compile the writing and its attached notes into one Markdown file, followed by a bibliography of the sources of the notes"
+    )]
+    Compile {
+        // The ID of the writing
+        #[clap(index = 1, required = true, value_name = "ID", verbatim_doc_comment)]
+        id: u64,
+
+        // The file to write to
+        #[clap(
+            short,
+            long,
+            value_name = "PATH",
+            default_value = "manuscript.md",
+            verbatim_doc_comment
+        )]
+        output: PathBuf,
+
+        // Put the topic of each note above it as a heading
+        #[clap(long, verbatim_doc_comment)]
+        headings: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SourceCommands {
+    #[command(
+        about = "list all sources",
+        long_about = "list all sources with their citation key, type, year and how many notes and attachments they have"
+    )]
+    List,
+    #[command(
+        about = "rename a source",
+        long_about = "rename a source, e.g. `cerebra source rename book \"Some Book\"`. Sources can be given by their id, name or citation key."
+    )]
+    Rename {
+        // The source to rename
+        #[clap(
+            index = 1,
+            required = true,
+            value_name = "SOURCE",
+            verbatim_doc_comment
+        )]
+        source: String,
+
+        // The new name of the source
+        #[clap(index = 2, required = true, value_name = "NAME", verbatim_doc_comment)]
+        name: String,
+    },
+    #[command(
+        about = "merge a source into another",
+        long_about = "merge the first source into the second, e.g. `cerebra source merge book \"Some Book\"`. Notes and attachments of the first source move to the second and the first one is deleted."
+    )]
+    Merge {
+        // The source to merge and delete
+        #[clap(index = 1, required = true, value_name = "FROM", verbatim_doc_comment)]
+        from: String,
+
+        // The source that is kept
+        #[clap(index = 2, required = true, value_name = "INTO", verbatim_doc_comment)]
+        into: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum IdeaCommands {
+    #[command(
+        about = "promote an idea to a project",
+        long_about = "promote an idea to a project that is linked to the topics of the idea and gets the idea text as its first note"
+    )]
+    Promote {
+        // The ID of the idea
+        #[clap(index = 1, required = true, value_name = "ID", verbatim_doc_comment)]
+        id: u64,
+
+        // The name of the project, defaults to the idea text
+        #[clap(long, verbatim_doc_comment)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReportCommands {
+    #[command(
+        about = "display a burndown chart of a project",
+        long_about = "display the open and completed tasks of a project per week, the weekly velocity and an estimate of when the project will be finished"
+    )]
+    Burndown {
+        // The name of the project, e.g. work or work/client
+        #[clap(
+            index = 1,
+            required = true,
+            value_name = "PROJECT",
+            verbatim_doc_comment
+        )]
+        project: String,
+    },
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
     let config_path = config::get_config_path();
     let config: config::Config = match &config_path {
-        Some(path) => config::read_config(&path).expect("Failed to read config file"),
+        Some(path) => config::read_config(path).expect("Failed to read config file"),
         None => config::Config::default(),
     };
 
@@ -255,18 +569,121 @@ fn main() -> Result<(), Box<dyn Error>> {
 
             commandline::init::init(config_path, &config).expect("Failed to initialize Cerebra")
         }
-        Some(Commands::Last { number, entry_type }) => {
+        Some(Commands::Last {
+            number,
+            entry_type,
+            all,
+        }) => {
             check_cerebra(&config);
-            commandline::last::last(&config.db_path, *number, entry_type)
+            commandline::last::last(&config.db_path, *number, entry_type, *all)
                 .expect("Failed to get last entries")
         }
+        Some(Commands::Next { number, all }) => {
+            check_cerebra(&config);
+            commandline::next::next(&config, *number, *all).expect("Failed to get next tasks")
+        }
+        Some(Commands::Deps { id }) => {
+            check_cerebra(&config);
+            commandline::deps::deps(&config, *id).expect("Failed to get task dependencies")
+        }
+        Some(Commands::Project { command }) => {
+            check_cerebra(&config);
+            match command {
+                ProjectCommands::Show { name } => {
+                    commandline::project::show(&config, name).expect("Failed to show project")
+                }
+            }
+        }
+        Some(Commands::Journal) => {
+            check_cerebra(&config);
+            commandline::journal::journal(&config).expect("Failed to write journal entry")
+        }
+        Some(Commands::Code { command }) => {
+            check_cerebra(&config);
+            match command {
+                CodeCommands::Cat { id } => {
+                    commandline::code::cat(&config, *id).expect("Failed to print code")
+                }
+                CodeCommands::Extract => {
+                    commandline::code::extract(&config).expect("Failed to extract code")
+                }
+                CodeCommands::Check => {
+                    commandline::code::check(&config).expect("Failed to check code")
+                }
+            }
+        }
+        Some(Commands::Writing { command }) => {
+            check_cerebra(&config);
+            match command {
+                WritingCommands::Stats => {
+                    commandline::writing::stats(&config).expect("Failed to get writing statistics")
+                }
+                WritingCommands::Attach { id, entries } => {
+                    commandline::writing::attach(&config, *id, entries)
+                        .expect("Failed to attach notes")
+                }
+                WritingCommands::Compile {
+                    id,
+                    output,
+                    headings,
+                } => commandline::writing::compile(&config, *id, &resolve_path(output)?, *headings)
+                    .expect("Failed to compile writing"),
+            }
+        }
+        Some(Commands::Source { command }) => {
+            check_cerebra(&config);
+            match command {
+                SourceCommands::List => {
+                    commandline::source::list(&config).expect("Failed to list sources")
+                }
+                SourceCommands::Rename { source, name } => {
+                    commandline::source::rename(&config, source, name)
+                        .expect("Failed to rename source")
+                }
+                SourceCommands::Merge { from, into } => {
+                    commandline::source::merge(&config, from, into)
+                        .expect("Failed to merge sources")
+                }
+            }
+        }
+        Some(Commands::Idea { command }) => {
+            check_cerebra(&config);
+            match command {
+                IdeaCommands::Promote { id, name } => {
+                    commandline::idea::promote(&config, *id, name.as_deref())
+                        .expect("Failed to promote idea")
+                }
+            }
+        }
+        Some(Commands::Start { id }) => {
+            check_cerebra(&config);
+            commandline::timesheet::start(&config, *id).expect("Failed to start task")
+        }
+        Some(Commands::Stop) => {
+            check_cerebra(&config);
+            commandline::timesheet::stop(&config).expect("Failed to stop task")
+        }
+        Some(Commands::Timesheet { week }) => {
+            check_cerebra(&config);
+            commandline::timesheet::timesheet(&config, *week).expect("Failed to get timesheet")
+        }
+        Some(Commands::Report { command }) => {
+            check_cerebra(&config);
+            match command {
+                ReportCommands::Burndown { project } => {
+                    commandline::report::burndown(&config, project)
+                        .expect("Failed to get burndown report")
+                }
+            }
+        }
         Some(Commands::Add {
             entry_type,
             content,
             tags,
+            edit,
         }) => {
             check_cerebra(&config);
-            commandline::add::add(&config, entry_type, content, tags.clone())?
+            commandline::add::add(&config, entry_type, content.as_deref(), tags.clone(), *edit)?
         }
         Some(Commands::Rm { entry_type, id }) => {
             check_cerebra(&config);
@@ -276,8 +693,15 @@ fn main() -> Result<(), Box<dyn Error>> {
             entry_type,
             id,
             tags,
-        }) => commandline::modify::mod_entry(&config, entry_type, *id, tags.clone())
-            .expect("Failed to modify entry"),
+        }) => {
+            check_cerebra(&config);
+            commandline::modify::mod_entry(&config, entry_type, *id, tags.clone())
+                .expect("Failed to modify entry")
+        }
+        Some(Commands::Edit { entry_type, id }) => {
+            check_cerebra(&config);
+            commandline::edit::edit(&config, entry_type, *id).expect("Failed to edit entry")
+        }
         Some(Commands::Search {
             entry_type,
             query,
@@ -287,6 +711,36 @@ fn main() -> Result<(), Box<dyn Error>> {
             commandline::search::search(&config, entry_type, query, *number)
                 .expect("Failed to search for entry")
         }
+        Some(Commands::Sync { sync_type }) => {
+            check_cerebra(&config);
+            commandline::sync::sync(&config, sync_type).expect("Failed to sync")
+        }
+        Some(Commands::Import { import_type, file }) => {
+            check_cerebra(&config);
+            commandline::import::import(&config, import_type, &resolve_path(file)?)
+                .expect("Failed to import entries")
+        }
+        Some(Commands::Attach { file, tags }) => {
+            check_cerebra(&config);
+            commandline::attach::attach(&config, &resolve_path(file)?, tags.clone())
+                .expect("Failed to attach file")
+        }
+        Some(Commands::Export {
+            export_type,
+            output,
+        }) => {
+            check_cerebra(&config);
+            let output = match output {
+                Some(output) => Some(resolve_path(output)?),
+                None => None,
+            };
+            commandline::export::export(&config, export_type, output.as_deref())
+                .expect("Failed to export entries")
+        }
+        Some(Commands::Check) => {
+            check_cerebra(&config);
+            commandline::check::check(&config).expect("Failed to check citations")
+        }
         Some(Commands::Draw) => {
             let conn = Connection::open(&config.db_path).expect("Failed to open database");
             commandline::draw::draw(&conn).expect("Failed to draw graph");
@@ -344,4 +798,12 @@ fn check_cerebra(config: &config::Config) {
             std::process::exit(1);
         }
     }
+
+    // databases created by older versions get the columns and tables added since
+    let mut conn = Connection::open(&config.db_path).expect("Failed to open database");
+    database::init::migrate(&mut conn).expect("Failed to migrate database");
+
+    // expired and recurring tasks are handled on every run, so there is no need for a daemon
+    database::task::expire(&conn).expect("Failed to expire tasks");
+    database::task::generate_recurring(&conn).expect("Failed to generate recurring tasks");
 }